@@ -0,0 +1,466 @@
+//! A module for looking up algorithms by the case they solve.
+//!
+//! An `AlgDatabase` holds cases from several sets, each with one or more
+//! algorithms and a list of free-form tags. The sets are:
+//!
+//! - `F2L`: inserting the front-right pair, with the cross and the other
+//!   three pairs solved; a pair in another slot is looked up after the y
+//!   rotation that brings it to the front right
+//! - `EOLL` and `OCLL`: orienting the last layer edges, then its corners
+//!   (two-look OLL)
+//! - `OLL`: orienting the whole last layer
+//! - `CPLL` and `EPLL`: permuting the last layer corners, then its edges
+//!   (two-look PLL)
+//! - `PLL`: permuting the whole last layer
+//! - `COLL`: solving the last layer corners while keeping the edges oriented
+//!
+//! A case is identified by its signature: the locations of the pieces the
+//! set tracks, and the orientations of the positions it cares about. When
+//! an algorithm is added, the case it solves is found by applying its
+//! inverse to the solved state; every further algorithm for the same case
+//! is checked by applying it to that state. Looking up a state returns the
+//! algorithms of its case with the turns of the U face (AUF) they need
+//! already added, seen from the current position of the centers.
+//!
+//! The built-in database is returned by `AlgDatabase::builtin()`. More
+//! algorithms can be loaded from CSV text, with one algorithm per line in
+//! the form `set,case,algorithm,tags`, where the tags are separated by
+//! spaces and may be left out. Blank lines and lines starting with `#` are
+//! skipped, and lines naming an existing case add to its algorithms.
+//!
+//! The OLL and PLL cases use their usual names, and the COLL cases the
+//! names given by `lastlayer`, whose index within each corner shape is
+//! opaque rather than the published numbering, e.g. `H#1`. The F2L cases use the standard numbering
+//! from 1 to 41: both pieces in the U layer (1 to 24), the corner in its
+//! slot (25 to 30), the edge in its slot (31 to 36), and both pieces in
+//! the slot (37 to 41).
+//!
+//! ```
+//! use rusty_rubik::algdb::*;
+//! use rusty_rubik::cube::*;
+//! use rusty_rubik::parser::parse_scramble;
+//!
+//! fn main() {
+//!     let db = AlgDatabase::from_csv("OLL,Sune,R U R' U R U2 R',2look").unwrap();
+//!
+//!     let seq = MoveSequence(parse_scramble("R U2 R' U' R U' R' U").unwrap());
+//!     let state = CubeState::default().apply_move_instances(&seq);
+//!     let found = db.lookup(&state, AlgSet::OLL).unwrap();
+//!
+//!     assert_eq!(found.case.name, "Sune");
+//!     assert_eq!(found.algorithms[0].to_string(), "U' R U R' U R U2 R'");
+//! }
+//! ```
+
+use crate::cube::*;
+use crate::lastlayer::{get_u_turn, normalize, AUF_TURNS};
+use crate::parser::parse_scramble;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+/// The sets of cases an algorithm can belong to.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, EnumString, Display)]
+pub enum AlgSet {
+    F2L,
+    EOLL,
+    OCLL,
+    OLL,
+    CPLL,
+    EPLL,
+    PLL,
+    COLL,
+}
+
+const LL: [usize; 4] = [0, 1, 2, 3];
+const F2L_CORNERS: [usize; 4] = [4, 5, 6, 7];
+const F2L_EDGES: [usize; 8] = [4, 5, 6, 7, 8, 9, 10, 11];
+/// The DFR corner and FR edge, which make up the F2L pair.
+const F2L_PAIR: (usize, usize) = (5, 6);
+
+/// The pieces a set tracks, and the positions whose orientation it cares
+/// about, in the order: corners, edges, corner positions, edge positions.
+fn get_scope(
+    set: AlgSet,
+) -> (
+    &'static [usize],
+    &'static [usize],
+    &'static [usize],
+    &'static [usize],
+) {
+    const ALL_CORNERS: [usize; 8] = [0, 1, 2, 3, 4, 5, 6, 7];
+    const ALL_EDGES: [usize; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+    match set {
+        AlgSet::F2L => (&F2L_CORNERS, &F2L_EDGES, &[], &[]),
+        AlgSet::EOLL => (&F2L_CORNERS, &F2L_EDGES, &[], &LL),
+        AlgSet::OCLL | AlgSet::OLL => (&F2L_CORNERS, &F2L_EDGES, &LL, &LL),
+        AlgSet::CPLL | AlgSet::COLL => (&ALL_CORNERS, &F2L_EDGES, &[], &LL),
+        AlgSet::EPLL | AlgSet::PLL => (&ALL_CORNERS, &ALL_EDGES, &[], &[]),
+    }
+}
+
+/// Returns the signature of a state in a set, which must have
+/// valid piece permutations.
+fn get_signature(set: AlgSet, state: &CubeState) -> Vec<u8> {
+    let (corners, edges, corner_positions, edge_positions) = get_scope(set);
+    let mut signature = vec![];
+    for &piece in corners {
+        let pos = state.cp.iter().position(|&p| p as usize == piece).unwrap();
+        signature.push(pos as u8);
+        signature.push(state.co[pos].rem_euclid(3) as u8);
+    }
+    for &piece in edges {
+        let pos = state.ep.iter().position(|&p| p as usize == piece).unwrap();
+        signature.push(pos as u8);
+        signature.push(state.eo[pos] as u8);
+    }
+    signature.extend(
+        corner_positions
+            .iter()
+            .map(|&pos| state.co[pos].rem_euclid(3) as u8),
+    );
+    signature.extend(edge_positions.iter().map(|&pos| state.eo[pos] as u8));
+    signature
+}
+
+fn get_y_turn(turns: u8) -> CubeState {
+    match Direction::from_num_turns(turns) {
+        Some(dir) => {
+            CubeState::default().apply_move_instance(&MoveInstance::new(BaseMoveToken::Y, dir))
+        }
+        None => CubeState::default(),
+    }
+}
+
+/// Returns `true` if a state solves its set, up to a turn of the U face.
+fn is_solved_in(set: AlgSet, state: &CubeState) -> bool {
+    let solved = get_signature(set, &CubeState::default());
+    AUF_TURNS
+        .iter()
+        .any(|&turns| get_signature(set, &state.compose(&get_u_turn(turns))) == solved)
+}
+
+/// Returns `true` if a state with its centers in their home positions is
+/// a case of a set: the pieces outside the set are solved, and the last
+/// layer is oriented and permuted as far as the earlier steps leave it.
+fn is_case_of(set: AlgSet, state: &CubeState) -> bool {
+    let corner_solved = |i: usize| state.cp[i] as usize == i && state.co[i] == 0;
+    let edge_solved = |i: usize| state.ep[i] as usize == i && state.eo[i] == 0;
+    let f2l_solved = match set {
+        AlgSet::F2L => {
+            F2L_CORNERS
+                .iter()
+                .all(|&i| i == F2L_PAIR.0 || corner_solved(i))
+                && F2L_EDGES.iter().all(|&i| i == F2L_PAIR.1 || edge_solved(i))
+        }
+        _ => {
+            F2L_CORNERS.iter().all(|&i| corner_solved(i))
+                && F2L_EDGES.iter().all(|&i| edge_solved(i))
+        }
+    };
+    let edges_oriented = LL.iter().all(|&i| state.eo[i] == 0);
+    let corners_oriented = LL.iter().all(|&i| state.co[i] == 0);
+    let corners_permuted = AUF_TURNS.iter().any(|&turns| {
+        let turned = state.compose(&get_u_turn(turns));
+        LL.iter().all(|&i| turned.cp[i] as usize == i)
+    });
+    f2l_solved
+        && match set {
+            AlgSet::F2L | AlgSet::EOLL | AlgSet::OLL => true,
+            AlgSet::OCLL | AlgSet::COLL => edges_oriented,
+            AlgSet::CPLL | AlgSet::PLL => edges_oriented && corners_oriented,
+            AlgSet::EPLL => edges_oriented && corners_oriented && corners_permuted,
+        }
+}
+
+/// An error encountered while adding algorithms to a database.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum AlgDatabaseError {
+    /// A line of CSV text does not have between three and four fields.
+    InvalidLine(String),
+    UnknownSet(String),
+    InvalidAlgorithm(String),
+    /// The algorithm does not solve a case of its set, e.g. an OLL
+    /// algorithm that breaks the first two layers.
+    NotInSet(String),
+    /// The algorithm does not solve the case it is listed under.
+    CaseMismatch(String, String),
+    /// The algorithm solves a case already listed under the given name.
+    DuplicateCase(String, String),
+    /// An error on the given line of CSV text, counting from 1.
+    AtLine(usize, Box<AlgDatabaseError>),
+}
+
+impl std::fmt::Display for AlgDatabaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlgDatabaseError::InvalidLine(line) => write!(f, "invalid line '{}'", line),
+            AlgDatabaseError::UnknownSet(set) => write!(f, "unknown set '{}'", set),
+            AlgDatabaseError::InvalidAlgorithm(alg) => write!(f, "invalid algorithm '{}'", alg),
+            AlgDatabaseError::NotInSet(alg) => {
+                write!(f, "'{}' does not solve a case of its set", alg)
+            }
+            AlgDatabaseError::CaseMismatch(case, alg) => {
+                write!(f, "'{}' does not solve case {}", alg, case)
+            }
+            AlgDatabaseError::DuplicateCase(case, alg) => {
+                write!(f, "'{}' solves case {}, which is already listed", alg, case)
+            }
+            AlgDatabaseError::AtLine(line, error) => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for AlgDatabaseError {}
+
+/// A case of a set, with the algorithms that solve it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AlgCase {
+    pub set: AlgSet,
+    pub name: String,
+    pub algorithms: Vec<MoveSequence>,
+    pub tags: Vec<String>,
+    /// A state of the case, solved by the first algorithm without any AUF.
+    state: CubeState,
+    /// The U turns each algorithm needs before and after it to solve `state`.
+    aufs: Vec<(u8, u8)>,
+}
+
+impl AlgCase {
+    /// Returns a state of this case, which is solved by the first
+    /// algorithm without any AUF.
+    pub fn get_state(&self) -> &CubeState {
+        &self.state
+    }
+}
+
+/// The case of a looked up state, with its algorithms adjusted for AUF.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AlgMatch<'a> {
+    pub case: &'a AlgCase,
+    /// The y rotation to make before the algorithms, which brings an F2L
+    /// pair in another slot to the front right. This is always `None`
+    /// for the other sets.
+    pub rotation: Option<Direction>,
+    /// The algorithms of the case, in order, each with the U turns
+    /// needed before and after it.
+    pub algorithms: Vec<MoveSequence>,
+}
+
+lazy_static! {
+    static ref BUILTIN: AlgDatabase =
+        AlgDatabase::from_csv(include_str!("algorithms.csv")).unwrap();
+}
+
+/// A collection of algorithms, indexed by the case they solve.
+#[derive(Debug, Clone, Default)]
+pub struct AlgDatabase {
+    cases: Vec<AlgCase>,
+    /// The case and the U turns before and after its first algorithm
+    /// that solve each signature of each set.
+    lookup: HashMap<(AlgSet, Vec<u8>), (usize, u8, u8)>,
+}
+
+/// Returns the U turns needed before and after an algorithm to solve a
+/// state in a set, if there are any.
+fn find_aufs(set: AlgSet, state: &CubeState, alg: &MoveSequence) -> Option<(u8, u8)> {
+    AUF_TURNS.iter().find_map(|&pre| {
+        let result = state.compose(&get_u_turn(pre)).apply_move_instances(alg);
+        AUF_TURNS
+            .iter()
+            .find(|&&post| is_solved_in(set, &result.compose(&get_u_turn(post))))
+            .map(|&post| (pre, post))
+    })
+}
+
+/// Returns an algorithm with the given numbers of U turns around it,
+/// merging them into the algorithm where possible.
+fn add_aufs(alg: &MoveSequence, pre: u8, post: u8) -> MoveSequence {
+    let u_turn = |turns| {
+        Direction::from_num_turns(turns).map(|dir| MoveInstance::new(BaseMoveToken::U, dir))
+    };
+    let mut moves = vec![];
+    moves.extend(u_turn(pre));
+    moves.extend(alg.get_moves());
+    moves.extend(u_turn(post));
+    MoveSequence(moves).simplify().0
+}
+
+impl AlgDatabase {
+    /// Returns the built-in database.
+    pub fn builtin() -> Self {
+        BUILTIN.clone()
+    }
+
+    /// Constructs a database from CSV text.
+    pub fn from_csv(text: &str) -> Result<Self, AlgDatabaseError> {
+        let mut db = AlgDatabase::default();
+        db.extend_from_csv(text)?;
+        Ok(db)
+    }
+
+    /// Adds the algorithms in CSV text to this database.
+    pub fn extend_from_csv(&mut self, text: &str) -> Result<(), AlgDatabaseError> {
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let at_line = |error| AlgDatabaseError::AtLine(i + 1, Box::new(error));
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() < 3 || fields.len() > 4 {
+                return Err(at_line(AlgDatabaseError::InvalidLine(line.to_string())));
+            }
+            let set = AlgSet::from_str(fields[0])
+                .map_err(|_| at_line(AlgDatabaseError::UnknownSet(fields[0].to_string())))?;
+            let alg = parse_scramble(fields[2])
+                .map_err(|_| at_line(AlgDatabaseError::InvalidAlgorithm(fields[2].to_string())))?;
+            let tags: Vec<&str> = fields
+                .get(3)
+                .map_or(vec![], |tags| tags.split_whitespace().collect());
+            self.add_algorithm(set, fields[1], MoveSequence(alg), &tags)
+                .map_err(at_line)?;
+        }
+        Ok(())
+    }
+
+    /// Adds an algorithm for a case, creating the case if it is new.
+    ///
+    /// The algorithm is checked by applying it: a new case must be a case
+    /// of its set, and an existing case must be solved by the algorithm.
+    pub fn add_algorithm(
+        &mut self,
+        set: AlgSet,
+        name: &str,
+        alg: MoveSequence,
+        tags: &[&str],
+    ) -> Result<(), AlgDatabaseError> {
+        let index = match self
+            .cases
+            .iter()
+            .position(|case| case.set == set && case.name == name)
+        {
+            Some(index) => {
+                let case = &mut self.cases[index];
+                let aufs = find_aufs(set, &case.state, &alg).ok_or_else(|| {
+                    AlgDatabaseError::CaseMismatch(name.to_string(), alg.to_string())
+                })?;
+                case.algorithms.push(alg);
+                case.aufs.push(aufs);
+                index
+            }
+            None => {
+                let state = normalize(&CubeState::default().apply_move_instances(&alg.invert()))
+                    .filter(|state| is_case_of(set, state))
+                    .ok_or_else(|| AlgDatabaseError::NotInSet(alg.to_string()))?;
+                if let Some((existing, _, _)) = self.lookup.get(&(set, get_signature(set, &state)))
+                {
+                    let existing = self.cases[*existing].name.clone();
+                    return Err(AlgDatabaseError::DuplicateCase(existing, alg.to_string()));
+                }
+                let index = self.cases.len();
+                // the state U^a S U^b is solved by U^-b, then the algorithm, then U^-a
+                for &pre in AUF_TURNS.iter() {
+                    for &post in AUF_TURNS.iter() {
+                        let variant = get_u_turn(post)
+                            .compose(&state)
+                            .compose(&get_u_turn((4 - pre) % 4));
+                        self.lookup
+                            .entry((set, get_signature(set, &variant)))
+                            .or_insert((index, pre, (4 - post) % 4));
+                    }
+                }
+                self.cases.push(AlgCase {
+                    set,
+                    name: name.to_string(),
+                    algorithms: vec![alg],
+                    tags: vec![],
+                    state,
+                    aufs: vec![(0, 0)],
+                });
+                index
+            }
+        };
+        let case_tags = &mut self.cases[index].tags;
+        for tag in tags {
+            if !case_tags.iter().any(|t| t == tag) {
+                case_tags.push(tag.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns all cases, in the order they were added.
+    pub fn get_cases(&self) -> &[AlgCase] {
+        &self.cases
+    }
+
+    /// Returns the case of a set with the given name.
+    pub fn get_case(&self, set: AlgSet, name: &str) -> Option<&AlgCase> {
+        self.cases
+            .iter()
+            .find(|case| case.set == set && case.name == name)
+    }
+
+    /// Returns the cases with the given tag.
+    pub fn get_cases_with_tag(&self, tag: &str) -> Vec<&AlgCase> {
+        self.cases
+            .iter()
+            .filter(|case| case.tags.iter().any(|t| t == tag))
+            .collect()
+    }
+
+    /// Returns the case of a state in a set, with its algorithms adjusted
+    /// for AUF, or `None` if the state is not a case in this database or
+    /// is not a valid state.
+    ///
+    /// The state may be turned by a y rotation, in which case the
+    /// algorithms are given as seen from the current position of the centers.
+    /// An F2L pair may be in any slot, with the rotation that brings it to
+    /// the front right given in the match.
+    pub fn lookup(&self, state: &CubeState, set: AlgSet) -> Option<AlgMatch<'_>> {
+        state.validate().ok()?;
+        let state = normalize(state)?;
+        let rotations: &[u8] = match set {
+            AlgSet::F2L => &AUF_TURNS,
+            _ => &[0],
+        };
+        // after the rotation Y, the state S is seen as Y^-1 S Y
+        let (turns, (index, pre, post)) = rotations.iter().find_map(|&turns| {
+            let rotated = get_y_turn((4 - turns) % 4)
+                .compose(&state)
+                .compose(&get_y_turn(turns));
+            self.lookup
+                .get(&(set, get_signature(set, &rotated)))
+                .map(|entry| (turns, entry))
+        })?;
+        let case = &self.cases[*index];
+        let algorithms = case
+            .algorithms
+            .iter()
+            .zip(case.aufs.iter())
+            .map(|(alg, (alg_pre, alg_post))| {
+                add_aufs(alg, (pre + alg_pre) % 4, (post + alg_post) % 4)
+            })
+            .collect();
+        Some(AlgMatch {
+            case,
+            rotation: Direction::from_num_turns(turns),
+            algorithms,
+        })
+    }
+
+    /// Returns the cases of a state in every set of this database.
+    pub fn lookup_all(&self, state: &CubeState) -> Vec<AlgMatch<'_>> {
+        let mut sets: Vec<AlgSet> = vec![];
+        for case in self.cases.iter() {
+            if !sets.contains(&case.set) {
+                sets.push(case.set);
+            }
+        }
+        sets.iter()
+            .filter_map(|&set| self.lookup(state, set))
+            .collect()
+    }
+}
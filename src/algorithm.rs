@@ -0,0 +1,238 @@
+//! A module for representing algorithms built from commutators,
+//! conjugates and repetitions.
+//!
+//! Algorithms are often written in a bracket notation, where $[A, B]$
+//! denotes the commutator $ABA^{-1}B^{-1}$, $[C: A]$ denotes the conjugate
+//! $CAC^{-1}$, and $(A)n$ denotes $A$ repeated $n$ times. These can be nested
+//! arbitrarily, e.g. `[U: [R D R', U2]]` or `(R U R' U')3`.
+//!
+//! Flat move sequences can be rewritten in this notation with `find_commutator`.
+//!
+//! ```
+//! use rusty_rubik::algorithm::Algorithm;
+//! use rusty_rubik::cube::*;
+//! use rusty_rubik::parser::parse_scramble;
+//!
+//! fn main() {
+//!     let a = Algorithm::Sequence(MoveSequence(parse_scramble("R U' R'").unwrap()));
+//!     let b = Algorithm::Sequence(MoveSequence(parse_scramble("D").unwrap()));
+//!     let comm = Algorithm::Commutator(Box::new(a), Box::new(b));
+//!
+//!     assert_eq!(comm.to_string(), "[R U' R', D]");
+//!     assert_eq!(comm.expand().to_string(), "R U' R' D R U R' D'");
+//! }
+//! ```
+
+use crate::cube::*;
+
+/// A recursive representation of an algorithm in bracket notation.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Algorithm {
+    /// A plain sequence of moves.
+    Sequence(MoveSequence),
+    /// Several algorithms performed one after another.
+    Concat(Vec<Algorithm>),
+    /// The commutator $[A, B] = ABA^{-1}B^{-1}$.
+    Commutator(Box<Algorithm>, Box<Algorithm>),
+    /// The conjugate $[C: A] = CAC^{-1}$.
+    Conjugate(Box<Algorithm>, Box<Algorithm>),
+    /// An algorithm repeated the given number of times.
+    Repeat(Box<Algorithm>, u32),
+}
+
+impl Algorithm {
+    /// Expands this algorithm into the sequence of moves it represents.
+    ///
+    /// No cancellation is done between the parts of the algorithm; use
+    /// `MoveSequence::simplify` on the result for that.
+    pub fn expand(&self) -> MoveSequence {
+        let mut moves = vec![];
+        self.expand_into(&mut moves);
+        MoveSequence(moves)
+    }
+
+    fn expand_into(&self, moves: &mut Vec<MoveInstance>) {
+        match self {
+            Algorithm::Sequence(seq) => moves.extend(seq.get_moves()),
+            Algorithm::Concat(algs) => {
+                for alg in algs {
+                    alg.expand_into(moves);
+                }
+            }
+            Algorithm::Commutator(a, b) => {
+                a.expand_into(moves);
+                b.expand_into(moves);
+                a.invert().expand_into(moves);
+                b.invert().expand_into(moves);
+            }
+            Algorithm::Conjugate(c, a) => {
+                c.expand_into(moves);
+                a.expand_into(moves);
+                c.invert().expand_into(moves);
+            }
+            Algorithm::Repeat(alg, n) => {
+                for _ in 0..*n {
+                    alg.expand_into(moves);
+                }
+            }
+        }
+    }
+
+    /// Applies this algorithm to a Rubik's Cube configuration.
+    pub fn apply(&self, state: &CubeState) -> CubeState {
+        state.apply_move_instances(&self.expand())
+    }
+
+    /// Returns the inverse of this algorithm, keeping its bracket structure.
+    ///
+    /// The inverse of $[A, B]$ is $[B, A]$, and the inverse of $[C: A]$
+    /// is $[C: A^{-1}]$.
+    pub fn invert(&self) -> Self {
+        match self {
+            Algorithm::Sequence(seq) => Algorithm::Sequence(seq.invert()),
+            Algorithm::Concat(algs) => {
+                Algorithm::Concat(algs.iter().rev().map(|alg| alg.invert()).collect())
+            }
+            Algorithm::Commutator(a, b) => Algorithm::Commutator(b.clone(), a.clone()),
+            Algorithm::Conjugate(c, a) => Algorithm::Conjugate(c.clone(), Box::new(a.invert())),
+            Algorithm::Repeat(alg, n) => Algorithm::Repeat(Box::new(alg.invert()), *n),
+        }
+    }
+}
+
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Algorithm::Sequence(seq) => write!(f, "{}", seq),
+            Algorithm::Concat(algs) => {
+                let strs: Vec<String> = algs
+                    .iter()
+                    .map(|alg| alg.to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+                write!(f, "{}", strs.join(" "))
+            }
+            Algorithm::Commutator(a, b) => write!(f, "[{}, {}]", a, b),
+            Algorithm::Conjugate(c, a) => write!(f, "[{}: {}]", c, a),
+            Algorithm::Repeat(alg, n) => match **alg {
+                // brackets already delimit commutators and conjugates
+                Algorithm::Commutator(..) | Algorithm::Conjugate(..) => write!(f, "{}{}", alg, n),
+                _ => write!(f, "({}){}", alg, n),
+            },
+        }
+    }
+}
+
+impl From<MoveSequence> for Algorithm {
+    fn from(seq: MoveSequence) -> Self {
+        Algorithm::Sequence(seq)
+    }
+}
+
+impl From<Commutator> for Algorithm {
+    fn from(comm: Commutator) -> Self {
+        let Commutator(a, b) = comm;
+        Algorithm::Commutator(Box::new(a.into()), Box::new(b.into()))
+    }
+}
+
+impl From<Conjugate> for Algorithm {
+    fn from(conj: Conjugate) -> Self {
+        let Conjugate(c, comm) = conj;
+        Algorithm::Conjugate(Box::new(c.into()), Box::new(comm.into()))
+    }
+}
+
+/// Returns the candidate first parts of a sequence, up to the given length.
+///
+/// Besides the prefixes of the sequence, the last move of each prefix is
+/// also tried in every direction, since that move may have merged with the
+/// move following it (e.g. the R in [R: U R'] merges into R2 when expanded).
+fn get_prefix_candidates(moves: &[MoveInstance], max_len: usize) -> Vec<MoveSequence> {
+    let mut candidates = vec![];
+    for len in 1..=std::cmp::min(max_len, moves.len()) {
+        let last = moves[len - 1];
+        for dir in [Direction::Normal, Direction::Prime, Direction::Double].iter() {
+            let mut prefix = moves[..len - 1].to_vec();
+            prefix.push(MoveInstance::new(last.basemove, *dir));
+            candidates.push(MoveSequence(prefix));
+        }
+    }
+    candidates
+}
+
+fn concat(seqs: &[&MoveSequence]) -> MoveSequence {
+    MoveSequence(
+        seqs.iter()
+            .flat_map(|seq| seq.get_moves().iter().copied())
+            .collect(),
+    )
+}
+
+/// Searches for a commutator $[A, B]$ expanding to the given simplified
+/// sequence, with $A$ and $B$ at most `max_len` moves long. Returns the
+/// parts with the fewest total moves.
+fn find_plain_commutator(
+    target: &MoveSequence,
+    max_len: usize,
+) -> Option<(MoveSequence, MoveSequence)> {
+    let mut best: Option<(MoveSequence, MoveSequence)> = None;
+    for a in get_prefix_candidates(target.get_moves(), max_len) {
+        let a_inv = a.invert();
+        // whatever follows A must start with B
+        let (rest, _) = concat(&[&a_inv, target]).simplify();
+        for b in get_prefix_candidates(rest.get_moves(), max_len) {
+            let len = a.get_moves().len() + b.get_moves().len();
+            if let Some((best_a, best_b)) = &best {
+                if len >= best_a.get_moves().len() + best_b.get_moves().len() {
+                    continue;
+                }
+            }
+            let (expanded, _) = concat(&[&a, &b, &a_inv, &b.invert()]).simplify();
+            if expanded == *target {
+                best = Some((a.clone(), b));
+            }
+        }
+    }
+    best
+}
+
+/// Searches for a concise bracket form of a sequence of moves, either a
+/// commutator $[A, B]$ or a conjugated commutator $[C: [A, B]]$.
+///
+/// Each of $A$, $B$ and $C$ is at most `max_len` moves long. The sequence
+/// is simplified first, and the form with the fewest moves in total is
+/// returned. If no such form exists within the length limit, `None` is
+/// returned.
+pub fn find_commutator(seq: &MoveSequence, max_len: usize) -> Option<Algorithm> {
+    let (target, _) = seq.simplify();
+    if target.get_moves().is_empty() {
+        return None;
+    }
+    let mut best: Option<(usize, Algorithm)> = None;
+    if let Some((a, b)) = find_plain_commutator(&target, max_len) {
+        let len = a.get_moves().len() + b.get_moves().len();
+        best = Some((
+            len,
+            Algorithm::Commutator(Box::new(a.into()), Box::new(b.into())),
+        ));
+    }
+    for c in get_prefix_candidates(target.get_moves(), max_len) {
+        let (inner, _) = concat(&[&c.invert(), &target, &c]).simplify();
+        // a conjugate that does not shorten the sequence is not useful
+        if inner.get_moves().len() >= target.get_moves().len() {
+            continue;
+        }
+        if let Some((a, b)) = find_plain_commutator(&inner, max_len) {
+            let len = c.get_moves().len() + a.get_moves().len() + b.get_moves().len();
+            if best.as_ref().is_none_or(|(l, _)| len < *l) {
+                let comm = Algorithm::Commutator(Box::new(a.into()), Box::new(b.into()));
+                best = Some((
+                    len,
+                    Algorithm::Conjugate(Box::new(c.into()), Box::new(comm)),
+                ));
+            }
+        }
+    }
+    best.map(|(_, alg)| alg)
+}
@@ -0,0 +1,302 @@
+//! A module for generating the memo of blindfolded solves.
+//!
+//! In blindfolded solving, each sticker of the corners and edges is given
+//! a letter, and the state is memorized as the sequence of stickers the
+//! buffer piece is swapped with to solve the cube. The letters are usually
+//! grouped in pairs, one pair for each algorithm performed.
+//!
+//! Tracing starts from the buffer: the target is the sticker where the
+//! buffer sticker belongs, then the sticker where the piece at that target
+//! belongs, and so on until the buffer's own piece is reached. The remaining
+//! unsolved pieces are then solved by cycle breaks, which start a new cycle
+//! at the unsolved piece with the alphabetically earliest letter. Pieces in
+//! their home positions that are only twisted or flipped are listed
+//! separately, and an odd number of corner targets indicates parity.
+//!
+//! The default lettering scheme is Speffz, with UFR and UF as buffers.
+//! The centers are assumed to be in their home positions.
+//!
+//! ```
+//! use rusty_rubik::bld::*;
+//! use rusty_rubik::cube::*;
+//! use rusty_rubik::parser::parse_scramble;
+//!
+//! fn main() {
+//!     let seq = MoveSequence(parse_scramble("U").unwrap());
+//!     let state = CubeState::default().apply_move_instances(&seq);
+//!     let memo = get_memo(&state, &BldConfig::default());
+//!
+//!     assert_eq!(memo.get_corner_pairs(), vec!["BA", "D"]);
+//!     assert_eq!(memo.get_edge_pairs(), vec!["BA", "D"]);
+//!     assert!(memo.parity);
+//! }
+//! ```
+
+use crate::cube::CubeState;
+use crate::cycle::{parse_piece, CycleNotationError, PieceType};
+
+/// The corner stickers, in the order their letters are listed in a
+/// `LetterScheme`: the U, L, F, R, B and D faces, each read clockwise
+/// starting from its top left corner.
+pub const CORNER_STICKERS: [&str; 24] = [
+    "UBL", "UBR", "UFR", "UFL", "LUB", "LUF", "LDF", "LDB", "FUL", "FUR", "FDR", "FDL", "RUF",
+    "RUB", "RDB", "RDF", "BUR", "BUL", "BDL", "BDR", "DFL", "DFR", "DBR", "DBL",
+];
+
+/// The edge stickers, in the order their letters are listed in a
+/// `LetterScheme`: the U, L, F, R, B and D faces, each read clockwise
+/// starting from its top edge.
+pub const EDGE_STICKERS: [&str; 24] = [
+    "UB", "UR", "UF", "UL", "LU", "LF", "LD", "LB", "FU", "FR", "FD", "FL", "RU", "RB", "RD", "RF",
+    "BU", "BL", "BD", "BR", "DF", "DR", "DB", "DL",
+];
+
+/// An assignment of letters to the stickers of the corners and edges.
+///
+/// The letters are given in the order of `CORNER_STICKERS` and
+/// `EDGE_STICKERS`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct LetterScheme {
+    pub corners: [char; 24],
+    pub edges: [char; 24],
+}
+
+const SPEFFZ: [char; 24] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X',
+];
+
+impl Default for LetterScheme {
+    /// The Speffz lettering scheme.
+    fn default() -> Self {
+        LetterScheme {
+            corners: SPEFFZ,
+            edges: SPEFFZ,
+        }
+    }
+}
+
+impl LetterScheme {
+    /// Constructs a scheme from the letters of the corner and edge
+    /// stickers, given in the order of `CORNER_STICKERS` and `EDGE_STICKERS`.
+    ///
+    /// Returns `None` if either string does not have exactly 24 letters.
+    pub fn new(corners: &str, edges: &str) -> Option<Self> {
+        let corners: Vec<char> = corners.chars().filter(|c| !c.is_whitespace()).collect();
+        let edges: Vec<char> = edges.chars().filter(|c| !c.is_whitespace()).collect();
+        if corners.len() != 24 || edges.len() != 24 {
+            return None;
+        }
+        let mut scheme = LetterScheme::default();
+        scheme.corners.copy_from_slice(&corners);
+        scheme.edges.copy_from_slice(&edges);
+        Some(scheme)
+    }
+
+    /// Returns the letter of a sticker of a piece position.
+    ///
+    /// Stickers are numbered as in `cycle::PieceCycle`.
+    pub fn get_letter(&self, piece_type: PieceType, pos: u8, sticker: u8) -> char {
+        let (names, letters) = match piece_type {
+            PieceType::Corner => (&CORNER_STICKERS, &self.corners),
+            PieceType::Edge => (&EDGE_STICKERS, &self.edges),
+        };
+        let i = names
+            .iter()
+            .position(|name| parse_piece(name) == Ok((piece_type, pos, sticker)))
+            .unwrap();
+        letters[i]
+    }
+}
+
+/// The settings used for generating a memo.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct BldConfig {
+    pub scheme: LetterScheme,
+    /// The position of the corner buffer, in the order used by `CubeState`.
+    pub corner_buffer: u8,
+    /// The position of the edge buffer, in the order used by `CubeState`.
+    pub edge_buffer: u8,
+}
+
+impl Default for BldConfig {
+    /// Speffz lettering with UFR and UF as buffers.
+    fn default() -> Self {
+        BldConfig {
+            scheme: LetterScheme::default(),
+            corner_buffer: 2,
+            edge_buffer: 2,
+        }
+    }
+}
+
+impl BldConfig {
+    /// Constructs the settings for a lettering scheme and the names
+    /// of the buffer pieces, e.g. `"UFR"` and `"DF"`.
+    pub fn new(
+        scheme: LetterScheme,
+        corner_buffer: &str,
+        edge_buffer: &str,
+    ) -> Result<Self, CycleNotationError> {
+        let corner_buffer = match parse_piece(corner_buffer)? {
+            (PieceType::Corner, pos, _) => pos,
+            _ => return Err(CycleNotationError::UnknownPiece(corner_buffer.to_string())),
+        };
+        let edge_buffer = match parse_piece(edge_buffer)? {
+            (PieceType::Edge, pos, _) => pos,
+            _ => return Err(CycleNotationError::UnknownPiece(edge_buffer.to_string())),
+        };
+        Ok(BldConfig {
+            scheme,
+            corner_buffer,
+            edge_buffer,
+        })
+    }
+}
+
+/// The memo of a state for a blindfolded solve.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BldMemo {
+    /// The corner targets, in the order they are solved.
+    pub corners: Vec<char>,
+    /// The edge targets, in the order they are solved.
+    pub edges: Vec<char>,
+    /// The corners which are twisted in their home positions, each given
+    /// by the letter of the sticker currently showing its U or D color.
+    /// The twist of the buffer follows from the others and is not listed.
+    pub twisted_corners: Vec<char>,
+    /// The edges which are flipped in their home positions, each given
+    /// by the letter of its sticker that is not the U or D sticker (or
+    /// the F or B sticker for the middle layer edges).
+    pub flipped_edges: Vec<char>,
+    /// `true` if there is an odd number of corner targets, so that two
+    /// corners and two edges are left swapped.
+    pub parity: bool,
+}
+
+fn get_pairs(letters: &[char]) -> Vec<String> {
+    letters
+        .chunks(2)
+        .map(|pair| pair.iter().collect())
+        .collect()
+}
+
+impl BldMemo {
+    /// Returns the corner targets grouped in pairs.
+    pub fn get_corner_pairs(&self) -> Vec<String> {
+        get_pairs(&self.corners)
+    }
+
+    /// Returns the edge targets grouped in pairs.
+    pub fn get_edge_pairs(&self) -> Vec<String> {
+        get_pairs(&self.edges)
+    }
+}
+
+impl std::fmt::Display for BldMemo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Edges: {}", self.get_edge_pairs().join(" "))?;
+        if !self.flipped_edges.is_empty() {
+            let flips: String = self.flipped_edges.iter().collect();
+            writeln!(f, "Flipped edges: {}", flips)?;
+        }
+        writeln!(f, "Corners: {}", self.get_corner_pairs().join(" "))?;
+        if !self.twisted_corners.is_empty() {
+            let twists: String = self.twisted_corners.iter().collect();
+            writeln!(f, "Twisted corners: {}", twists)?;
+        }
+        write!(f, "Parity: {}", if self.parity { "yes" } else { "no" })
+    }
+}
+
+/// Traces the targets of one kind of piece, returning the targets and
+/// the pieces that are misoriented in their home positions.
+fn trace_pieces(
+    perm: &[u8],
+    ori: &[u8],
+    piece_type: PieceType,
+    buffer: u8,
+    scheme: &LetterScheme,
+) -> (Vec<char>, Vec<char>) {
+    let n = piece_type.get_num_orientations();
+    let letter = |(pos, sticker): (u8, u8)| scheme.get_letter(piece_type, pos, sticker);
+    // the home sticker of the sticker currently at a location
+    let home =
+        |(pos, sticker): (u8, u8)| (perm[pos as usize], (sticker + n - ori[pos as usize]) % n);
+
+    let mut solved = vec![false; perm.len()];
+    solved[buffer as usize] = true;
+    let mut misoriented = vec![];
+    for pos in 0..perm.len() {
+        if perm[pos] as usize == pos && pos != buffer as usize {
+            solved[pos] = true;
+            if ori[pos] != 0 {
+                // the sticker now showing the piece's first sticker
+                let sticker = if piece_type == PieceType::Corner {
+                    ori[pos]
+                } else {
+                    1
+                };
+                misoriented.push(letter((pos as u8, sticker)));
+            }
+        }
+    }
+
+    let mut targets = vec![];
+    let mut current = (buffer, 0);
+    let mut cycle_start = buffer;
+    loop {
+        let target = home(current);
+        if target.0 != cycle_start {
+            targets.push(letter(target));
+            solved[target.0 as usize] = true;
+            current = target;
+            continue;
+        }
+        if cycle_start != buffer {
+            // close the cycle, bringing the buffer piece back to the buffer
+            targets.push(letter(target));
+        }
+        // break into the unsolved piece with the earliest letter
+        let next = (0..perm.len() as u8)
+            .filter(|&pos| !solved[pos as usize])
+            .min_by_key(|&pos| letter((pos, 0)));
+        match next {
+            Some(pos) => {
+                targets.push(letter((pos, 0)));
+                solved[pos as usize] = true;
+                cycle_start = pos;
+                current = (pos, 0);
+            }
+            None => break,
+        }
+    }
+    (targets, misoriented)
+}
+
+/// Generates the memo of a state for a blindfolded solve.
+pub fn get_memo(state: &CubeState, config: &BldConfig) -> BldMemo {
+    let co = state.co.map(|x| (x + 3) as u8 % 3);
+    let eo = state.eo.map(|x| x as u8);
+    let (corners, twisted_corners) = trace_pieces(
+        &state.cp,
+        &co,
+        PieceType::Corner,
+        config.corner_buffer,
+        &config.scheme,
+    );
+    let (edges, flipped_edges) = trace_pieces(
+        &state.ep,
+        &eo,
+        PieceType::Edge,
+        config.edge_buffer,
+        &config.scheme,
+    );
+    BldMemo {
+        parity: corners.len() % 2 == 1,
+        corners,
+        edges,
+        twisted_corners,
+        flipped_edges,
+    }
+}
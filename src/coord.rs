@@ -0,0 +1,334 @@
+//! A module for applying moves to the Rubik's Cube at the coordinate level.
+//!
+//! Instead of permuting the arrays of a `CubeState`, a state is described
+//! by a handful of integers (coordinates), and each of the 18 moves of
+//! `ALL_MOVES` is applied by looking up the next value of each coordinate
+//! in a precomputed move table. The coordinates are:
+//!
+//! - the corner twist, in `[0, 3^7 - 1]`
+//! - the edge flip, in `[0, 2^11 - 1]`
+//! - the corner permutation rank, in `[0, 8! - 1]`
+//! - the locations of the edges UB UR UF UL, of BL BR FR FL and of
+//!   DF DR DB DL, each in `[0, 12 * 11 * 10 * 9 - 1]`
+//!
+//! The first three are the same values that make up the indices returned by
+//! `get_index_of_state`. A table over the full edge permutation rank would
+//! have $12!$ rows, so the edges are split into three groups of four
+//! instead, whose locations share a single move table.
+//!
+//! ```
+//! use rusty_rubik::coord::*;
+//! use rusty_rubik::cube::*;
+//!
+//! fn main() {
+//!     let tables = &*MOVE_TABLES;
+//!     let mut coord = CoordState::solved();
+//!     // R U, using the indices of the moves in ALL_MOVES
+//!     coord = tables.apply_move(&coord, 9);
+//!     coord = tables.apply_move(&coord, 0);
+//!
+//!     let state = CubeState::default()
+//!         .apply_move_instance(&ALL_MOVES[9])
+//!         .apply_move_instance(&ALL_MOVES[0]);
+//!     assert_eq!(coord, CoordState::from_state(&state));
+//!     assert_eq!(get_index_of_coord(&coord), get_index_of_state(&state));
+//! }
+//! ```
+
+use crate::cube::*;
+use lazy_static::lazy_static;
+
+/// The number of values of the corner twist coordinate.
+pub const N_TWIST: usize = 2187;
+/// The number of values of the edge flip coordinate.
+pub const N_FLIP: usize = 2048;
+/// The number of values of the corner permutation coordinate.
+pub const N_CORNER_PERM: usize = 40320;
+/// The number of values of each edge location coordinate.
+pub const N_EDGE_LOCATIONS: usize = 11880;
+/// The number of sets of four edge positions.
+pub(crate) const N_EDGE_SETS: usize = 495;
+
+const FACTORIALS: [u64; 12] = [
+    1, 1, 2, 6, 24, 120, 720, 5040, 40320, 362880, 3628800, 39916800,
+];
+
+lazy_static! {
+    /// The move tables, computed the first time they are used.
+    pub static ref MOVE_TABLES: MoveTables = MoveTables::new();
+}
+
+/// A configuration of the Rubik's Cube given by its coordinates.
+///
+/// The centers are not tracked, since none of the moves in `ALL_MOVES`
+/// move them. A state whose centers have moved must first be rotated
+/// back with `CubeState::get_home_rotation`, as the solvers do.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct CoordState {
+    pub twist: u16,
+    pub flip: u16,
+    pub corner_perm: u16,
+    pub edges: [u16; 3],
+}
+
+/// Returns the coordinate of the locations of four edges.
+fn get_index_of_locations(locations: &[u8; 4]) -> u16 {
+    let mut used = [false; 12];
+    let mut index = 0;
+    for (i, &loc) in locations.iter().enumerate() {
+        let rank = (0..loc).filter(|&l| !used[l as usize]).count() as u16;
+        index = index * (12 - i as u16) + rank;
+        used[loc as usize] = true;
+    }
+    index
+}
+
+/// Returns the locations of four edges from their coordinate.
+pub(crate) fn get_locations_of_index(index: u16) -> [u8; 4] {
+    let mut ranks = [0; 4];
+    let mut index = index;
+    for i in (0..4).rev() {
+        let base = 12 - i as u16;
+        ranks[i] = index % base;
+        index /= base;
+    }
+    let mut used = [false; 12];
+    let mut locations = [0; 4];
+    for (loc, &rank) in locations.iter_mut().zip(ranks.iter()) {
+        let free = (0..12_u8).filter(|&l| !used[l as usize]).nth(rank as usize);
+        *loc = free.unwrap();
+        used[*loc as usize] = true;
+    }
+    locations
+}
+
+impl CoordState {
+    /// Returns the coordinates of the solved state.
+    pub fn solved() -> Self {
+        Self::from_state(&CubeState::default())
+    }
+
+    /// Returns the coordinates of a state, ignoring its centers.
+    pub fn from_state(state: &CubeState) -> Self {
+        let mut edges = [0; 3];
+        for (group, edge) in edges.iter_mut().enumerate() {
+            let mut locations = [0; 4];
+            for (k, loc) in locations.iter_mut().enumerate() {
+                let piece = (4 * group + k) as u8;
+                *loc = state.ep.iter().position(|&p| p == piece).unwrap() as u8;
+            }
+            *edge = get_index_of_locations(&locations);
+        }
+        CoordState {
+            twist: get_index_of_orientation(&state.co, 3),
+            flip: get_index_of_orientation(&state.eo, 2),
+            corner_perm: get_index_of_permutation(&state.cp) as u16,
+            edges,
+        }
+    }
+
+    /// Returns the state with these coordinates, with the centers
+    /// in their home positions.
+    pub fn to_state(&self) -> CubeState {
+        let mut ep = [0; 12];
+        for (group, edge) in self.edges.iter().enumerate() {
+            for (k, loc) in get_locations_of_index(*edge).iter().enumerate() {
+                ep[*loc as usize] = (4 * group + k) as u8;
+            }
+        }
+        let (cp, co) =
+            get_corners_of_index(self.corner_perm as u32 * N_TWIST as u32 + self.twist as u32);
+        CubeState {
+            cp,
+            co,
+            ep,
+            eo: get_eo_of_index(self.flip),
+            ..CubeState::default()
+        }
+    }
+}
+
+/// Returns the corner, edge orientation (EO) and edge permutation (EP)
+/// indices of a state given by its coordinates, as `get_index_of_state` does.
+pub fn get_index_of_coord(coord: &CoordState) -> (u32, u16, u64) {
+    let corner_index = coord.corner_perm as u32 * N_TWIST as u32 + coord.twist as u32;
+    (corner_index, coord.flip, get_ep_index_of_coord(coord))
+}
+
+/// Returns the edge permutation (EP) index of a state given by its coordinates.
+///
+/// The index is the rank of the permutation, i.e. the sum over every
+/// position $i$ of $(11 - i)!$ times the number of smaller pieces after
+/// it. Each group of edges contributes the pairs within the group, which
+/// only depend on its locations, and the pairs with each lower group,
+/// which only depend on the sets of positions of both groups, so the
+/// index is a sum of six table lookups.
+fn get_ep_index_of_coord(coord: &CoordState) -> u64 {
+    let tables = &*MOVE_TABLES;
+    let [a, b, c] = coord.edges.map(|e| e as usize);
+    let [set_a, set_b, set_c] = [a, b, c].map(|e| tables.edge_sets[e] as usize);
+    let within: u64 = [a, b, c]
+        .iter()
+        .map(|&e| tables.inversions_within[e] as u64)
+        .sum();
+    let between: u64 = [(set_b, set_a), (set_c, set_a), (set_c, set_b)]
+        .iter()
+        .map(|&(upper, lower)| tables.inversions_between[upper * N_EDGE_SETS + lower] as u64)
+        .sum();
+    within + between
+}
+
+/// Returns the positions in a bitmask of edge positions, in order.
+fn get_positions_of_mask(mask: u16) -> impl Iterator<Item = usize> {
+    (0..12).filter(move |&i| mask & (1 << i) != 0)
+}
+
+/// The precomputed move tables of each coordinate.
+///
+/// Row `i` of a table gives the value of the coordinate after applying
+/// each of the moves of `ALL_MOVES`, in order, to a state where the
+/// coordinate has value `i`.
+pub struct MoveTables {
+    pub twist: Vec<[u16; 18]>,
+    pub flip: Vec<[u16; 18]>,
+    pub corner_perm: Vec<[u16; 18]>,
+    pub edge_locations: Vec<[u16; 18]>,
+    /// The set of positions of each edge location coordinate, numbered
+    /// in the order of their bitmasks.
+    pub(crate) edge_sets: Vec<u16>,
+    /// The part of the EP index from the pairs of edges within a group,
+    /// for each edge location coordinate.
+    inversions_within: Vec<u32>,
+    /// The part of the EP index from the pairs of edges of two groups,
+    /// for each set of positions of the upper group and of the lower one.
+    inversions_between: Vec<u32>,
+}
+
+/// Builds the move table of a coordinate with `size` values, given a
+/// function from a coordinate value to some state having that value, and
+/// a function from a state back to its coordinate value.
+fn build_table(
+    size: usize,
+    to_state: &dyn Fn(usize) -> CubeState,
+    from_state: &dyn Fn(&CubeState) -> u16,
+) -> Vec<[u16; 18]> {
+    (0..size)
+        .map(|i| {
+            let state = to_state(i);
+            let mut row = [0; 18];
+            for (entry, m) in row.iter_mut().zip(ALL_MOVES.iter()) {
+                *entry = from_state(&state.apply_move_instance(m));
+            }
+            row
+        })
+        .collect()
+}
+
+impl MoveTables {
+    /// Computes the move tables. This takes a moment, so the shared
+    /// tables in `MOVE_TABLES` should usually be used instead.
+    pub fn new() -> Self {
+        let twist = build_table(
+            N_TWIST,
+            &|i| CubeState {
+                co: get_corners_of_index(i as u32).1,
+                ..CubeState::default()
+            },
+            &|state| get_index_of_orientation(&state.co, 3),
+        );
+        let flip = build_table(
+            N_FLIP,
+            &|i| CubeState {
+                eo: get_eo_of_index(i as u16),
+                ..CubeState::default()
+            },
+            &|state| get_index_of_orientation(&state.eo, 2),
+        );
+        let corner_perm = build_table(
+            N_CORNER_PERM,
+            &|i| CubeState {
+                cp: get_corners_of_index(i as u32 * N_TWIST as u32).0,
+                ..CubeState::default()
+            },
+            &|state| get_index_of_permutation(&state.cp) as u16,
+        );
+        // only the first group of edges is needed, as the move table
+        // depends on the locations alone
+        let edge_locations = build_table(
+            N_EDGE_LOCATIONS,
+            &|i| {
+                let locations = get_locations_of_index(i as u16);
+                let mut ep = [0; 12];
+                let mut others = 4..12;
+                for (loc, piece) in ep.iter_mut().enumerate() {
+                    *piece = match locations.iter().position(|&l| l as usize == loc) {
+                        Some(k) => k as u8,
+                        None => others.next().unwrap(),
+                    };
+                }
+                CubeState {
+                    ep,
+                    ..CubeState::default()
+                }
+            },
+            &|state| CoordState::from_state(state).edges[0],
+        );
+        let masks: Vec<u16> = (0_u16..1 << 12)
+            .filter(|mask| mask.count_ones() == 4)
+            .collect();
+        let mut edge_sets = vec![0; N_EDGE_LOCATIONS];
+        let mut inversions_within = vec![0; N_EDGE_LOCATIONS];
+        for i in 0..N_EDGE_LOCATIONS {
+            let locations = get_locations_of_index(i as u16);
+            let mask = locations.iter().fold(0, |mask, &l| mask | 1 << l);
+            edge_sets[i] = masks.binary_search(&mask).unwrap() as u16;
+            // piece k is followed by each smaller piece at a later location
+            inversions_within[i] = (0..4)
+                .map(|k| {
+                    let later = (0..k).filter(|&j| locations[j] > locations[k]).count();
+                    later as u32 * FACTORIALS[11 - locations[k] as usize] as u32
+                })
+                .sum();
+        }
+        let mut inversions_between = vec![0; N_EDGE_SETS * N_EDGE_SETS];
+        for (upper, &upper_mask) in masks.iter().enumerate() {
+            for (lower, &lower_mask) in masks.iter().enumerate() {
+                inversions_between[upper * N_EDGE_SETS + lower] = get_positions_of_mask(upper_mask)
+                    .map(|i| {
+                        let later = get_positions_of_mask(lower_mask).filter(|&j| j > i).count();
+                        later as u32 * FACTORIALS[11 - i] as u32
+                    })
+                    .sum();
+            }
+        }
+        MoveTables {
+            twist,
+            flip,
+            corner_perm,
+            edge_locations,
+            edge_sets,
+            inversions_within,
+            inversions_between,
+        }
+    }
+
+    /// Applies the move at index `m` of `ALL_MOVES` to a state.
+    pub fn apply_move(&self, coord: &CoordState, m: usize) -> CoordState {
+        CoordState {
+            twist: self.twist[coord.twist as usize][m],
+            flip: self.flip[coord.flip as usize][m],
+            corner_perm: self.corner_perm[coord.corner_perm as usize][m],
+            edges: [
+                self.edge_locations[coord.edges[0] as usize][m],
+                self.edge_locations[coord.edges[1] as usize][m],
+                self.edge_locations[coord.edges[2] as usize][m],
+            ],
+        }
+    }
+}
+
+impl Default for MoveTables {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -6,10 +6,15 @@
 //! orientation, edge permutation, and edge orientation. A tuple
 //! of these four properties (with correct parity relations)
 //! uniquely determines the state of the cube.
+//!
+//! Slice moves and whole-cube rotations also move the centers, so the
+//! permutation of the centers is tracked as well. For states reached
+//! using only face moves, the centers are always in their home positions.
 
+use crate::algorithm::Algorithm;
 use strum_macros::EnumString;
 
-/// An enum for the faces of the Rubik's Cube.
+/// An enum for the moves of the Rubik's Cube.
 ///
 /// - U: top face
 /// - D: bottom face
@@ -17,7 +22,14 @@ use strum_macros::EnumString;
 /// - R: right face
 /// - F: front face
 /// - B: back face
-#[derive(PartialEq, Eq, EnumString, Debug, Clone, Copy)]
+/// - Uw, Dw, Lw, Rw, Fw, Bw: a face together with its adjacent middle
+///   slice (wide moves), also written in lowercase (e.g. `r`)
+/// - M: middle slice between L and R, turning like L
+/// - E: equatorial slice between U and D, turning like D
+/// - S: standing slice between F and B, turning like F
+/// - X, Y, Z: whole-cube rotations, turning like R, U and F respectively,
+///   written in lowercase (`x`, `y`, `z`)
+#[derive(PartialEq, Eq, EnumString, Debug, Clone, Copy, Hash)]
 pub enum BaseMoveToken {
     U,
     D,
@@ -25,24 +37,72 @@ pub enum BaseMoveToken {
     R,
     F,
     B,
+    #[strum(serialize = "Uw", serialize = "u")]
+    Uw,
+    #[strum(serialize = "Dw", serialize = "d")]
+    Dw,
+    #[strum(serialize = "Lw", serialize = "l")]
+    Lw,
+    #[strum(serialize = "Rw", serialize = "r")]
+    Rw,
+    #[strum(serialize = "Fw", serialize = "f")]
+    Fw,
+    #[strum(serialize = "Bw", serialize = "b")]
+    Bw,
+    M,
+    E,
+    S,
+    #[strum(serialize = "x")]
+    X,
+    #[strum(serialize = "y")]
+    Y,
+    #[strum(serialize = "z")]
+    Z,
 }
 
 impl std::fmt::Display for BaseMoveToken {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            BaseMoveToken::X => write!(f, "x"),
+            BaseMoveToken::Y => write!(f, "y"),
+            BaseMoveToken::Z => write!(f, "z"),
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
 /// Represents the direction which to turn a face. `Prime` represents
 /// a counter-clockwise rotation of a face, and `Double` represents
 /// a 180 degree rotation of a face.
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
 pub enum Direction {
     Normal,
     Prime,
     Double,
 }
 
+impl Direction {
+    /// Returns the number of clockwise quarter turns this direction represents.
+    pub fn get_num_turns(&self) -> u8 {
+        match self {
+            Direction::Normal => 1,
+            Direction::Double => 2,
+            Direction::Prime => 3,
+        }
+    }
+
+    /// Returns the direction representing the given number of clockwise
+    /// quarter turns, or `None` if the turns cancel out.
+    pub fn from_num_turns(turns: u8) -> Option<Self> {
+        match turns % 4 {
+            1 => Some(Direction::Normal),
+            2 => Some(Direction::Double),
+            3 => Some(Direction::Prime),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Display for Direction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -54,7 +114,7 @@ impl std::fmt::Display for Direction {
 }
 
 /// An instantiation of a certain face equipped with a direction.
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
 pub struct MoveInstance {
     pub basemove: BaseMoveToken,
     pub dir: Direction,
@@ -85,6 +145,7 @@ impl std::fmt::Display for MoveInstance {
 
 /// A struct representing sequences of moves, used for representing
 /// scramble sequences and solution sequences.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
 pub struct MoveSequence(pub Vec<MoveInstance>);
 
 impl MoveSequence {
@@ -102,6 +163,47 @@ impl MoveSequence {
         }
         MoveSequence(moves)
     }
+
+    /// Simplifies this sequence, returning the simplified sequence together
+    /// with the number of moves that cancelled.
+    ///
+    /// Consecutive moves that turn about the same axis commute, so they are
+    /// gathered together: moves of the same kind are merged (e.g. R R becomes R2,
+    /// and R L R becomes L R2), moves that amount to nothing are removed, and the
+    /// remaining moves are put in a canonical order (U before D, L before R,
+    /// F before B). Cancellations cascade, so R U U' R' simplifies to nothing.
+    pub fn simplify(&self) -> (Self, usize) {
+        // each block holds the quarter turns of consecutive same-axis moves
+        let mut blocks: Vec<(u8, Vec<(BaseMoveToken, u8)>)> = vec![];
+        for m in self.get_moves() {
+            let axis = get_axis(m.basemove);
+            let turns = m.dir.get_num_turns();
+            match blocks.last_mut() {
+                Some((last_axis, block)) if *last_axis == axis => {
+                    match block.iter_mut().find(|(b, _)| *b == m.basemove) {
+                        Some((_, t)) => *t = (*t + turns) % 4,
+                        None => block.push((m.basemove, turns)),
+                    }
+                    block.retain(|(_, t)| *t != 0);
+                    if block.is_empty() {
+                        blocks.pop();
+                    }
+                }
+                _ => blocks.push((axis, vec![(m.basemove, turns)])),
+            }
+        }
+        let mut moves = vec![];
+        for (_, mut block) in blocks {
+            block.sort_by_key(|(b, _)| *b as u8);
+            for (basemove, turns) in block {
+                if let Some(dir) = Direction::from_num_turns(turns) {
+                    moves.push(MoveInstance::new(basemove, dir));
+                }
+            }
+        }
+        let cancelled = self.get_moves().len() - moves.len();
+        (MoveSequence(moves), cancelled)
+    }
 }
 
 impl std::fmt::Display for MoveSequence {
@@ -134,11 +236,27 @@ impl std::fmt::Display for MoveSequence {
 ///
 ///     // commutator representing [R U' R', D] = R U' R' D R U R' D'
 ///     let comm = Commutator(a,b);
-///     
+///     assert_eq!(comm.expand().to_string(), "R U' R' D R U R' D'");
 /// }
 /// ```
+///
+/// For commutators of arbitrary algorithms, see `algorithm::Algorithm`.
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Commutator(pub MoveSequence, pub MoveSequence);
 
+impl Commutator {
+    /// Expands this commutator into the sequence of moves it represents.
+    pub fn expand(&self) -> MoveSequence {
+        Algorithm::from(self.clone()).expand()
+    }
+}
+
+impl std::fmt::Display for Commutator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Algorithm::from(self.clone()))
+    }
+}
+
 /// A struct representing a conjugate, taking the form of a tuple.
 ///
 /// If the first element of this tuple is $C$, and the second is a commutator $B$,
@@ -164,23 +282,41 @@ pub struct Commutator(pub MoveSequence, pub MoveSequence);
 ///     ]);
 ///
 ///     // conjugate representing [R: [R D R', U2]] = R2 D R' U2 R D' R' U2 R'
-///     let comm = Commutator(a,b);
-///     
+///     let conj = Conjugate(c, Commutator(a,b));
+///     assert_eq!(conj.expand().simplify().0.to_string(), "R2 D R' U2 R D' R' U2 R'");
 /// }
 /// ```
+///
+/// For conjugates of arbitrary algorithms, see `algorithm::Algorithm`.
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Conjugate(pub MoveSequence, pub Commutator);
 
+impl Conjugate {
+    /// Expands this conjugate into the sequence of moves it represents.
+    pub fn expand(&self) -> MoveSequence {
+        Algorithm::from(self.clone()).expand()
+    }
+}
+
+impl std::fmt::Display for Conjugate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Algorithm::from(self.clone()))
+    }
+}
+
 /// An internal set of permutation vectors representing what action
 /// is done to a configuration of the Rubik's Cube when a move is applied.
 ///
 /// The order of the corners and edges is as follows:
 /// - Corners: UBL UBR UFR UFL DFL DFR DBR DBL
 /// - Edges: UB UR UF UL BL BR FR FL DF DR DB DL
-struct Move {
+/// - Centers: U D L R F B
+pub(crate) struct Move {
     pub cp_change: [u8; 8], // a[i] gives the position that i goes to
     pub co_change: [i8; 8],
     pub ep_change: [u8; 12],
     pub eo_change: [i8; 12],
+    pub center_change: [u8; 6],
 }
 
 /// A shorthand macro that can be used to construct MoveInstances.
@@ -235,14 +371,15 @@ macro_rules! apply_orientation {
     }};
 }
 
+// moves other than face moves share the bit of the face they turn with
 pub(crate) fn get_basemove_pos(token: BaseMoveToken) -> u8 {
     match token {
-        BaseMoveToken::U => 5,
-        BaseMoveToken::D => 4,
-        BaseMoveToken::L => 3,
-        BaseMoveToken::R => 2,
-        BaseMoveToken::F => 1,
-        BaseMoveToken::B => 0,
+        BaseMoveToken::U | BaseMoveToken::Uw | BaseMoveToken::Y => 5,
+        BaseMoveToken::D | BaseMoveToken::Dw | BaseMoveToken::E => 4,
+        BaseMoveToken::L | BaseMoveToken::Lw | BaseMoveToken::M => 3,
+        BaseMoveToken::R | BaseMoveToken::Rw | BaseMoveToken::X => 2,
+        BaseMoveToken::F | BaseMoveToken::Fw | BaseMoveToken::S | BaseMoveToken::Z => 1,
+        BaseMoveToken::B | BaseMoveToken::Bw => 0,
     }
 }
 
@@ -254,9 +391,23 @@ fn get_antipode(token: BaseMoveToken) -> BaseMoveToken {
         BaseMoveToken::R => BaseMoveToken::L,
         BaseMoveToken::F => BaseMoveToken::B,
         BaseMoveToken::B => BaseMoveToken::F,
+        BaseMoveToken::Uw => BaseMoveToken::Dw,
+        BaseMoveToken::Dw => BaseMoveToken::Uw,
+        BaseMoveToken::Lw => BaseMoveToken::Rw,
+        BaseMoveToken::Rw => BaseMoveToken::Lw,
+        BaseMoveToken::Fw => BaseMoveToken::Bw,
+        BaseMoveToken::Bw => BaseMoveToken::Fw,
+        // slices and rotations have no opposite counterpart
+        x => x,
     }
 }
 
+/// Returns the axis a move turns about: 0 for U/D, 1 for L/R and 2 for F/B.
+/// Moves about the same axis commute with each other.
+pub(crate) fn get_axis(token: BaseMoveToken) -> u8 {
+    2 - get_basemove_pos(token) / 2
+}
+
 // bitvector: [UDLRFB], 0 means it's allowed
 pub(crate) fn get_allowed_post_moves(prev_bv: u8, last_move: Option<BaseMoveToken>) -> u8 {
     if let Some(lm) = last_move {
@@ -301,26 +452,84 @@ pub fn allowed_moves_after_seq(moves: &MoveSequence) -> u8 {
 }
 
 /// The underlying struct for representing a configuration of the Rubik's Cube.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CubeState {
     pub cp: [u8; 8],
     pub co: [i8; 8],
     pub ep: [u8; 12],
     pub eo: [i8; 12],
+    pub centers: [u8; 6],
+}
+
+/// The reasons why a `CubeState` may fail to represent a solvable
+/// configuration of the Rubik's Cube.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum InvalidStateError {
+    /// The corner permutation is not a permutation of the 8 corners.
+    InvalidCornerPermutation,
+    /// The edge permutation is not a permutation of the 12 edges.
+    InvalidEdgePermutation,
+    /// A corner orientation is not one of -1, 0 or 1. The index
+    /// of the offending corner is given.
+    InvalidCornerOrientation(usize),
+    /// An edge orientation is not one of 0 or 1. The index
+    /// of the offending edge is given.
+    InvalidEdgeOrientation(usize),
+    /// The corner orientations do not sum to 0 mod 3, i.e. a single
+    /// corner has been twisted in place.
+    TwistedCorner,
+    /// The edge orientations do not sum to 0 mod 2, i.e. a single
+    /// edge has been flipped in place.
+    FlippedEdge,
+    /// The corner and edge permutations have different parities, i.e.
+    /// two pieces have been swapped.
+    ParitySwap,
+    /// The centers are not in any orientation reachable by rotating the cube.
+    InvalidCenters,
 }
 
+impl std::fmt::Display for InvalidStateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidStateError::InvalidCornerPermutation => {
+                write!(f, "corner permutation is not a permutation of 0..8")
+            }
+            InvalidStateError::InvalidEdgePermutation => {
+                write!(f, "edge permutation is not a permutation of 0..12")
+            }
+            InvalidStateError::InvalidCornerOrientation(i) => {
+                write!(f, "corner {} has an orientation outside of -1, 0, 1", i)
+            }
+            InvalidStateError::InvalidEdgeOrientation(i) => {
+                write!(f, "edge {} has an orientation outside of 0, 1", i)
+            }
+            InvalidStateError::TwistedCorner => write!(f, "a corner is twisted"),
+            InvalidStateError::FlippedEdge => write!(f, "an edge is flipped"),
+            InvalidStateError::ParitySwap => {
+                write!(f, "corner and edge permutation parities do not match")
+            }
+            InvalidStateError::InvalidCenters => {
+                write!(f, "centers are not in a reachable orientation")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidStateError {}
+
 impl Default for CubeState {
     fn default() -> CubeState {
         CubeState {
             cp: [0, 1, 2, 3, 4, 5, 6, 7],
-            co: [0 as i8; 8],
+            co: [0_i8; 8],
             ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
-            eo: [0 as i8; 12],
+            eo: [0_i8; 12],
+            centers: [0, 1, 2, 3, 4, 5],
         }
     }
 }
 
-fn get_move_matrix(mov: &BaseMoveToken) -> Move {
+pub(crate) fn get_move_matrix(mov: &BaseMoveToken) -> Move {
     match mov {
         BaseMoveToken::U => MOVE_U,
         BaseMoveToken::D => MOVE_D,
@@ -328,6 +537,18 @@ fn get_move_matrix(mov: &BaseMoveToken) -> Move {
         BaseMoveToken::R => MOVE_R,
         BaseMoveToken::F => MOVE_F,
         BaseMoveToken::B => MOVE_B,
+        BaseMoveToken::Uw => MOVE_UW,
+        BaseMoveToken::Dw => MOVE_DW,
+        BaseMoveToken::Lw => MOVE_LW,
+        BaseMoveToken::Rw => MOVE_RW,
+        BaseMoveToken::Fw => MOVE_FW,
+        BaseMoveToken::Bw => MOVE_BW,
+        BaseMoveToken::M => MOVE_M,
+        BaseMoveToken::E => MOVE_E,
+        BaseMoveToken::S => MOVE_S,
+        BaseMoveToken::X => MOVE_X,
+        BaseMoveToken::Y => MOVE_Y,
+        BaseMoveToken::Z => MOVE_Z,
     }
 }
 
@@ -342,7 +563,7 @@ fn factorial(num: u32) -> u32 {
 // range:
 // corners: [0, 8! - 1]
 // edges: [0, 12! - 1]
-fn get_index_of_permutation(perm: &[u8]) -> u32 {
+pub(crate) fn get_index_of_permutation(perm: &[u8]) -> u32 {
     // 2 bytes suffice for 12!
     let mut fin = 0;
     for i in 0..perm.len() {
@@ -354,13 +575,13 @@ fn get_index_of_permutation(perm: &[u8]) -> u32 {
         }
         fin += res * factorial((perm.len() - i - 1) as u32);
     }
-    fin as u32
+    fin
 }
 
 // range:
 // corners: [0, 3^7 - 1]
 // edges: [0, 2^11 - 1]
-fn get_index_of_orientation(ori: &[i8], num_orientations: u8) -> u16 {
+pub(crate) fn get_index_of_orientation(ori: &[i8], num_orientations: u8) -> u16 {
     let mut result = 0;
     for (i, val) in ori.iter().enumerate() {
         if i == ori.len() - 1 {
@@ -390,6 +611,90 @@ pub fn get_index_of_state(state: &CubeState) -> (u32, u16, u64) {
     (corner_index, eo_index, ep_index)
 }
 
+// inverse of get_index_of_permutation
+pub(crate) fn get_permutation_of_index(index: u32, len: usize) -> Vec<u8> {
+    let mut remaining: Vec<u8> = (0..len as u8).collect();
+    let mut perm = Vec::with_capacity(len);
+    let mut index = index;
+    for i in 0..len {
+        let f = factorial((len - i - 1) as u32);
+        perm.push(remaining.remove((index / f) as usize));
+        index %= f;
+    }
+    perm
+}
+
+// inverse of get_index_of_orientation; the orientation of the last
+// piece is determined by the others
+pub(crate) fn get_orientation_of_index(index: u16, len: usize, num_orientations: u8) -> Vec<i8> {
+    let n = num_orientations as u16;
+    let mut ori = vec![0_i8; len];
+    let mut index = index;
+    let mut sum = 0;
+    for i in (0..len - 1).rev() {
+        let val = (index % n) as i8;
+        index /= n;
+        sum += val;
+        ori[i] = val;
+    }
+    ori[len - 1] = (num_orientations as i8 - sum % num_orientations as i8) % num_orientations as i8;
+    if num_orientations == 3 {
+        for val in ori.iter_mut() {
+            if *val == 2 {
+                *val = -1;
+            }
+        }
+    }
+    ori
+}
+
+/// Returns the corner permutation and orientation of a corner index,
+/// as given by `get_index_of_state`.
+pub fn get_corners_of_index(index: u32) -> ([u8; 8], [i8; 8]) {
+    let mut cp = [0; 8];
+    let mut co = [0; 8];
+    cp.copy_from_slice(&get_permutation_of_index(index / u32::pow(3, 7), 8));
+    co.copy_from_slice(&get_orientation_of_index(
+        (index % u32::pow(3, 7)) as u16,
+        8,
+        3,
+    ));
+    (cp, co)
+}
+
+/// Returns the edge orientation of an EO index, as given by `get_index_of_state`.
+pub fn get_eo_of_index(index: u16) -> [i8; 12] {
+    let mut eo = [0; 12];
+    eo.copy_from_slice(&get_orientation_of_index(index, 12, 2));
+    eo
+}
+
+/// Returns the edge permutation of an EP index, as given by `get_index_of_state`.
+pub fn get_ep_of_index(index: u64) -> [u8; 12] {
+    let mut ep = [0; 12];
+    ep.copy_from_slice(&get_permutation_of_index(index as u32, 12));
+    ep
+}
+
+/// Returns the Rubik's Cube configuration with the given corner,
+/// edge orientation (EO) and edge permutation (EP) indices, with the
+/// centers in their home positions. This is the inverse of
+/// `get_index_of_state`.
+///
+/// Each valid index corresponds to exactly one state, but the state
+/// may not be solvable if the indices are chosen independently, since
+/// the corner and edge permutations must have the same parity.
+pub fn get_state_of_index(corner_index: u32, eo_index: u16, ep_index: u64) -> CubeState {
+    let (cp, co) = get_corners_of_index(corner_index);
+    CubeState {
+        cp,
+        co,
+        ep: get_ep_of_index(ep_index),
+        eo: get_eo_of_index(eo_index),
+        ..CubeState::default()
+    }
+}
+
 impl CubeState {
     fn apply_basemove(&self, m: &BaseMoveToken) -> Self {
         let mov = get_move_matrix(m);
@@ -400,17 +705,13 @@ impl CubeState {
             co: apply_permutation!(oriented_corners, &mov.cp_change),
             ep: apply_permutation!(&self.ep, &mov.ep_change),
             eo: apply_permutation!(oriented_edges, &mov.ep_change),
+            centers: apply_permutation!(&self.centers, &mov.center_change),
         }
     }
 
     /// Applies a move to a Rubik's Cube configuration.
     pub fn apply_move_instance(&self, m: &MoveInstance) -> Self {
-        let num_turns = match &m.dir {
-            Direction::Normal => 1,
-            Direction::Prime => 3,
-            Direction::Double => 2,
-        };
-        (0..num_turns).fold(self.clone(), |acc, _| acc.apply_basemove(&m.basemove))
+        (0..m.dir.get_num_turns()).fold(self.clone(), |acc, _| acc.apply_basemove(&m.basemove))
     }
 
     /// Applies a sequence of moves, in order to a Rubik's Cube configuration.
@@ -418,12 +719,322 @@ impl CubeState {
         moves
             .get_moves()
             .iter()
-            .fold(self.clone(), |acc, mov| acc.apply_move_instance(&mov))
+            .fold(self.clone(), |acc, mov| acc.apply_move_instance(mov))
     }
 
-    // pub fn random() -> Self {
+    /// Checks that this state is a solvable configuration of the Rubik's Cube.
+    ///
+    /// The solvers assume a solvable state and will search forever otherwise,
+    /// so hand-typed or scanned states should be validated first.
+    pub fn validate(&self) -> Result<(), InvalidStateError> {
+        if !is_permutation(&self.cp) {
+            return Err(InvalidStateError::InvalidCornerPermutation);
+        }
+        if !is_permutation(&self.ep) {
+            return Err(InvalidStateError::InvalidEdgePermutation);
+        }
+        if !is_center_orientation(&self.centers) {
+            return Err(InvalidStateError::InvalidCenters);
+        }
+        if let Some(i) = self.co.iter().position(|&x| !(-1..=1).contains(&x)) {
+            return Err(InvalidStateError::InvalidCornerOrientation(i));
+        }
+        if let Some(i) = self.eo.iter().position(|&x| x != 0 && x != 1) {
+            return Err(InvalidStateError::InvalidEdgeOrientation(i));
+        }
+        if self.co.iter().map(|&x| x as i32).sum::<i32>().rem_euclid(3) != 0 {
+            return Err(InvalidStateError::TwistedCorner);
+        }
+        if self.eo.iter().map(|&x| x as i32).sum::<i32>() % 2 != 0 {
+            return Err(InvalidStateError::FlippedEdge);
+        }
+        // slice moves change the parity of the edges and centers together
+        if permutation_parity(&self.cp)
+            != permutation_parity(&self.ep) ^ permutation_parity(&self.centers)
+        {
+            return Err(InvalidStateError::ParitySwap);
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if this state is a solvable configuration of the Rubik's Cube.
+    pub fn is_solvable(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Returns the whole-cube rotation, made of x, y and z moves, that
+    /// brings the centers of this state back to their home positions, or
+    /// `None` if the centers are not in a valid orientation.
+    ///
+    /// The solvers only turn the faces, which never move the centers, so
+    /// they solve the state reached after this rotation instead.
+    pub fn get_home_rotation(&self) -> Option<MoveSequence> {
+        use BaseMoveToken::*;
+        use Direction::*;
+        // first bring the U center back to the top, then turn about it
+        let tops = [
+            vec![],
+            vec![MoveInstance::new(X, Normal)],
+            vec![MoveInstance::new(X, Double)],
+            vec![MoveInstance::new(X, Prime)],
+            vec![MoveInstance::new(Z, Normal)],
+            vec![MoveInstance::new(Z, Prime)],
+        ];
+        let turns = [None, Some(Normal), Some(Double), Some(Prime)];
+        tops.iter()
+            .flat_map(|top| {
+                turns.iter().map(move |turn| {
+                    let mut moves = top.clone();
+                    moves.extend(turn.map(|dir| MoveInstance::new(Y, dir)));
+                    MoveSequence(moves)
+                })
+            })
+            .find(|rotation| {
+                self.apply_move_instances(rotation).centers == CubeState::default().centers
+            })
+    }
+
+    /// Generates a uniformly random, solvable configuration of the Rubik's Cube.
+    ///
+    /// Every reachable state is equally likely to be returned, which makes
+    /// this suitable for WCA-style random-state scrambles.
+    pub fn random() -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::random_with_seed(seed)
+    }
 
-    // }
+    /// Generates a uniformly random, solvable configuration of the Rubik's Cube
+    /// from the given seed. The same seed always yields the same state.
+    pub fn random_with_seed(seed: u64) -> Self {
+        let mut rng = XorShiftRng::new(seed);
+        let mut cp = [0, 1, 2, 3, 4, 5, 6, 7];
+        let mut ep = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+        rng.shuffle(&mut cp);
+        rng.shuffle(&mut ep);
+        // corner and edge permutations must share the same parity
+        if permutation_parity(&cp) != permutation_parity(&ep) {
+            ep.swap(10, 11);
+        }
+
+        // the orientation of the last piece is fixed by the others
+        let mut co = [0_i8; 8];
+        let mut co_sum = 0;
+        for c in co.iter_mut().take(7) {
+            let twist = rng.gen_range(3) as i8;
+            co_sum += twist;
+            *c = if twist == 2 { -1 } else { twist };
+        }
+        co[7] = match (3 - co_sum % 3) % 3 {
+            2 => -1,
+            x => x,
+        };
+
+        let mut eo = [0_i8; 12];
+        let mut eo_sum = 0;
+        for e in eo.iter_mut().take(11) {
+            *e = rng.gen_range(2) as i8;
+            eo_sum += *e;
+        }
+        eo[11] = eo_sum % 2;
+
+        CubeState {
+            cp,
+            co,
+            ep,
+            eo,
+            ..CubeState::default()
+        }
+    }
+
+    /// Returns the state reached by performing `other` on this state.
+    ///
+    /// If `self` is reached from the solved state by the moves $A$ and
+    /// `other` by the moves $B$, the result is the state reached by $AB$.
+    pub fn compose(&self, other: &CubeState) -> Self {
+        let mut result = CubeState::default();
+        for i in 0..8 {
+            let from = other.cp[i] as usize;
+            result.cp[i] = self.cp[from];
+            result.co[i] = normalize_twist(self.co[from] + other.co[i]);
+        }
+        for i in 0..12 {
+            let from = other.ep[i] as usize;
+            result.ep[i] = self.ep[from];
+            result.eo[i] = (self.eo[from] + other.eo[i]) % 2;
+        }
+        for i in 0..6 {
+            result.centers[i] = self.centers[other.centers[i] as usize];
+        }
+        result
+    }
+
+    /// Returns the state which undoes this one, so that composing the two
+    /// in either order gives the solved state.
+    ///
+    /// If this state is reached by the moves $A$, its inverse is the state
+    /// reached by $A^{-1}$.
+    pub fn inverse(&self) -> Self {
+        let mut result = CubeState::default();
+        for i in 0..8 {
+            let to = self.cp[i] as usize;
+            result.cp[to] = i as u8;
+            result.co[to] = normalize_twist(-self.co[i]);
+        }
+        for i in 0..12 {
+            let to = self.ep[i] as usize;
+            result.ep[to] = i as u8;
+            result.eo[to] = self.eo[i];
+        }
+        for i in 0..6 {
+            result.centers[self.centers[i] as usize] = i as u8;
+        }
+        result
+    }
+
+    /// Returns `true` if this is the solved state, with the cube held in
+    /// its original orientation.
+    pub fn is_identity(&self) -> bool {
+        *self == CubeState::default()
+    }
+
+    /// Returns the order of this state, the smallest positive number of
+    /// times it must be performed before the cube returns to the solved state.
+    ///
+    /// For example, the order of the state reached by `R U` is 105.
+    pub fn order(&self) -> u64 {
+        let corners = get_cycle_order(&self.cp, &self.co, 3);
+        let edges = get_cycle_order(&self.ep, &self.eo, 2);
+        let centers = get_cycle_order(&self.centers, &[0; 6], 1);
+        num::integer::lcm(num::integer::lcm(corners, edges), centers)
+    }
+}
+
+/// Maps a corner twist onto the range used by `CubeState`.
+pub(crate) fn normalize_twist(twist: i8) -> i8 {
+    match twist.rem_euclid(3) {
+        2 => -1,
+        x => x,
+    }
+}
+
+/// Returns the order of a permutation of pieces with orientations.
+///
+/// A cycle of length $n$ returns its pieces to their positions after
+/// $n$ repetitions, but they are only oriented correctly as well once
+/// the total twist along the cycle has been repeated away.
+fn get_cycle_order(perm: &[u8], ori: &[i8], num_orientations: i8) -> u64 {
+    let mut visited = vec![false; perm.len()];
+    let mut order = 1;
+    for start in 0..perm.len() {
+        if visited[start] {
+            continue;
+        }
+        let mut len = 0;
+        let mut twist = 0;
+        let mut i = start;
+        while !visited[i] {
+            visited[i] = true;
+            twist += ori[i];
+            len += 1;
+            i = perm[i] as usize;
+        }
+        if twist.rem_euclid(num_orientations) != 0 {
+            len *= num_orientations as u64;
+        }
+        order = num::integer::lcm(order, len);
+    }
+    order
+}
+
+/// Returns `true` if the given array contains each of `0..len` exactly once.
+fn is_permutation(perm: &[u8]) -> bool {
+    let mut seen = vec![false; perm.len()];
+    for &p in perm {
+        if p as usize >= perm.len() || seen[p as usize] {
+            return false;
+        }
+        seen[p as usize] = true;
+    }
+    true
+}
+
+/// Returns `true` if the given centers can be reached from the home
+/// positions by whole-cube rotations.
+fn is_center_orientation(centers: &[u8; 6]) -> bool {
+    // x and y rotations generate all 24 orientations of the cube
+    let mut orientations = vec![[0, 1, 2, 3, 4, 5]];
+    let mut i = 0;
+    while i < orientations.len() {
+        for mov in [MOVE_X, MOVE_Y].iter() {
+            let next = apply_permutation!(&orientations[i], &mov.center_change);
+            if !orientations.contains(&next) {
+                orientations.push(next);
+            }
+        }
+        i += 1;
+    }
+    orientations.contains(centers)
+}
+
+/// Returns `true` if the given permutation is odd.
+fn permutation_parity(perm: &[u8]) -> bool {
+    let mut odd = false;
+    for i in 0..perm.len() {
+        for j in (i + 1)..perm.len() {
+            if perm[i] > perm[j] {
+                odd = !odd;
+            }
+        }
+    }
+    odd
+}
+
+/// A small xorshift pseudo-random number generator, used for
+/// generating random states without pulling in an external crate.
+pub(crate) struct XorShiftRng(u64);
+
+impl XorShiftRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // run the seed through splitmix64 so that nearby seeds
+        // give unrelated streams, and so that the state is never zero
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        XorShiftRng(if z == 0 { 1 } else { z })
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Returns a uniformly distributed integer in `[0, n)`.
+    pub(crate) fn gen_range(&mut self, n: u64) -> u64 {
+        // reject the tail of the range to avoid modulo bias
+        let zone = u64::MAX - (u64::MAX % n);
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+
+    /// Shuffles a slice in place with the Fisher-Yates algorithm.
+    pub(crate) fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.gen_range(i as u64 + 1) as usize;
+            slice.swap(i, j);
+        }
+    }
 }
 
 /// A vector of all allowed moves on a Rubik's Cube.
@@ -453,6 +1064,7 @@ const MOVE_U: Move = Move {
     co_change: [0, 0, 0, 0, 0, 0, 0, 0],
     ep_change: [1, 2, 3, 0, 4, 5, 6, 7, 8, 9, 10, 11],
     eo_change: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    center_change: [0, 1, 2, 3, 4, 5],
 };
 
 const MOVE_D: Move = Move {
@@ -460,6 +1072,7 @@ const MOVE_D: Move = Move {
     co_change: [0, 0, 0, 0, 0, 0, 0, 0],
     ep_change: [0, 1, 2, 3, 4, 5, 6, 7, 9, 10, 11, 8],
     eo_change: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    center_change: [0, 1, 2, 3, 4, 5],
 };
 
 const MOVE_R: Move = Move {
@@ -467,6 +1080,7 @@ const MOVE_R: Move = Move {
     co_change: [0, -1, 1, 0, 0, -1, 1, 0],
     ep_change: [0, 5, 2, 3, 4, 9, 1, 7, 8, 6, 10, 11],
     eo_change: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    center_change: [0, 1, 2, 3, 4, 5],
 };
 
 const MOVE_L: Move = Move {
@@ -474,6 +1088,7 @@ const MOVE_L: Move = Move {
     co_change: [1, 0, 0, -1, 1, 0, 0, -1],
     ep_change: [0, 1, 2, 7, 3, 5, 6, 11, 8, 9, 10, 4],
     eo_change: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    center_change: [0, 1, 2, 3, 4, 5],
 };
 
 const MOVE_F: Move = Move {
@@ -481,6 +1096,7 @@ const MOVE_F: Move = Move {
     co_change: [0, 0, -1, 1, -1, 1, 0, 0],
     ep_change: [0, 1, 6, 3, 4, 5, 8, 2, 7, 9, 10, 11],
     eo_change: [0, 0, 1, 0, 0, 0, 1, 1, 1, 0, 0, 0],
+    center_change: [0, 1, 2, 3, 4, 5],
 };
 
 const MOVE_B: Move = Move {
@@ -488,4 +1104,101 @@ const MOVE_B: Move = Move {
     co_change: [-1, 1, 0, 0, 0, 0, -1, 1],
     ep_change: [4, 1, 2, 3, 10, 0, 6, 7, 8, 9, 5, 11],
     eo_change: [1, 0, 0, 0, 1, 1, 0, 0, 0, 0, 1, 0],
+    center_change: [0, 1, 2, 3, 4, 5],
+};
+
+const MOVE_UW: Move = Move {
+    cp_change: [1, 2, 3, 0, 4, 5, 6, 7],
+    co_change: [0, 0, 0, 0, 0, 0, 0, 0],
+    ep_change: [1, 2, 3, 0, 5, 6, 7, 4, 8, 9, 10, 11],
+    eo_change: [0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0],
+    center_change: [0, 1, 5, 4, 2, 3],
+};
+
+const MOVE_DW: Move = Move {
+    cp_change: [0, 1, 2, 3, 5, 6, 7, 4],
+    co_change: [0, 0, 0, 0, 0, 0, 0, 0],
+    ep_change: [0, 1, 2, 3, 7, 4, 5, 6, 9, 10, 11, 8],
+    eo_change: [0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0],
+    center_change: [0, 1, 4, 5, 3, 2],
+};
+
+const MOVE_RW: Move = Move {
+    cp_change: [0, 6, 1, 3, 4, 2, 5, 7],
+    co_change: [0, -1, 1, 0, 0, -1, 1, 0],
+    ep_change: [10, 5, 0, 3, 4, 9, 1, 7, 2, 6, 8, 11],
+    eo_change: [1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0],
+    center_change: [5, 4, 2, 3, 0, 1],
+};
+
+const MOVE_LW: Move = Move {
+    cp_change: [3, 1, 2, 4, 7, 5, 6, 0],
+    co_change: [1, 0, 0, -1, 1, 0, 0, -1],
+    ep_change: [2, 1, 8, 7, 3, 5, 6, 11, 10, 9, 0, 4],
+    eo_change: [1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0],
+    center_change: [4, 5, 2, 3, 1, 0],
+};
+
+const MOVE_FW: Move = Move {
+    cp_change: [0, 1, 5, 2, 3, 4, 6, 7],
+    co_change: [0, 0, -1, 1, -1, 1, 0, 0],
+    ep_change: [0, 9, 6, 1, 4, 5, 8, 2, 7, 11, 10, 3],
+    eo_change: [0, 1, 1, 1, 0, 0, 1, 1, 1, 1, 0, 1],
+    center_change: [3, 2, 0, 1, 4, 5],
+};
+
+const MOVE_BW: Move = Move {
+    cp_change: [7, 0, 2, 3, 4, 5, 1, 6],
+    co_change: [-1, 1, 0, 0, 0, 0, -1, 1],
+    ep_change: [4, 3, 2, 11, 10, 0, 6, 7, 8, 1, 5, 9],
+    eo_change: [1, 1, 0, 1, 1, 1, 0, 0, 0, 1, 1, 1],
+    center_change: [2, 3, 1, 0, 4, 5],
+};
+
+const MOVE_M: Move = Move {
+    cp_change: [0, 1, 2, 3, 4, 5, 6, 7],
+    co_change: [0, 0, 0, 0, 0, 0, 0, 0],
+    ep_change: [2, 1, 8, 3, 4, 5, 6, 7, 10, 9, 0, 11],
+    eo_change: [1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0],
+    center_change: [4, 5, 2, 3, 1, 0],
+};
+
+const MOVE_E: Move = Move {
+    cp_change: [0, 1, 2, 3, 4, 5, 6, 7],
+    co_change: [0, 0, 0, 0, 0, 0, 0, 0],
+    ep_change: [0, 1, 2, 3, 7, 4, 5, 6, 8, 9, 10, 11],
+    eo_change: [0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0],
+    center_change: [0, 1, 4, 5, 3, 2],
+};
+
+const MOVE_S: Move = Move {
+    cp_change: [0, 1, 2, 3, 4, 5, 6, 7],
+    co_change: [0, 0, 0, 0, 0, 0, 0, 0],
+    ep_change: [0, 9, 2, 1, 4, 5, 6, 7, 8, 11, 10, 3],
+    eo_change: [0, 1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1],
+    center_change: [3, 2, 0, 1, 4, 5],
+};
+
+const MOVE_X: Move = Move {
+    cp_change: [7, 6, 1, 0, 3, 2, 5, 4],
+    co_change: [1, -1, 1, -1, 1, -1, 1, -1],
+    ep_change: [10, 5, 0, 4, 11, 9, 1, 3, 2, 6, 8, 7],
+    eo_change: [1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0],
+    center_change: [5, 4, 2, 3, 0, 1],
+};
+
+const MOVE_Y: Move = Move {
+    cp_change: [1, 2, 3, 0, 7, 4, 5, 6],
+    co_change: [0, 0, 0, 0, 0, 0, 0, 0],
+    ep_change: [1, 2, 3, 0, 5, 6, 7, 4, 11, 8, 9, 10],
+    eo_change: [0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0],
+    center_change: [0, 1, 5, 4, 2, 3],
+};
+
+const MOVE_Z: Move = Move {
+    cp_change: [1, 6, 5, 2, 3, 4, 7, 0],
+    co_change: [-1, 1, -1, 1, -1, 1, -1, 1],
+    ep_change: [5, 9, 6, 1, 0, 10, 8, 2, 7, 11, 4, 3],
+    eo_change: [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    center_change: [3, 2, 0, 1, 4, 5],
 };
@@ -0,0 +1,347 @@
+//! A module for describing states of the Rubik's Cube by their piece cycles.
+//!
+//! A state is written in Singmaster's cycle notation, as a list of disjoint
+//! cycles such as `(UFR UBR UBL)(UF UR)+`. Each cycle lists the positions
+//! its pieces visit: the piece at the first position moves to the second,
+//! the piece at the second moves to the third, and so on, with the piece at
+//! the last position moving back to the first.
+//!
+//! The order of the letters of each position encodes the orientation of the
+//! pieces. The first letter of each name is the face of the sticker being
+//! traced, so that in `(UFR RUB)` the U sticker of the UFR piece ends up on
+//! the R face of the UBR position. A cycle whose traced sticker does not
+//! return to where it started is followed by `+` if the pieces end up twisted
+//! clockwise (or flipped, for edges) and `-` if twisted counter-clockwise.
+//! A single twisted corner or flipped edge is written as a cycle of length
+//! one, e.g. `(UFR)+`.
+//!
+//! Only the corners and edges are described; the centers are ignored.
+//!
+//! ```
+//! use rusty_rubik::cube::*;
+//! use rusty_rubik::parser::parse_scramble;
+//!
+//! fn main() {
+//!     let seq = MoveSequence(parse_scramble("U").unwrap());
+//!     let state = CubeState::default().apply_move_instances(&seq);
+//!     let notation = state.to_cycle_notation();
+//!
+//!     assert_eq!(notation, "(UBL UBR UFR UFL)(UB UR UF UL)");
+//!     assert_eq!(CubeState::from_cycle_notation(&notation), Ok(state));
+//! }
+//! ```
+
+use crate::cube::CubeState;
+use crate::facelet::{ColorScheme, Face, CORNER_COLORS, EDGE_COLORS};
+
+/// The names of the corner positions, in the order used by `CubeState`.
+pub const CORNER_NAMES: [&str; 8] = ["UBL", "UBR", "UFR", "UFL", "DFL", "DFR", "DBR", "DBL"];
+
+/// The names of the edge positions, in the order used by `CubeState`.
+pub const EDGE_NAMES: [&str; 12] = [
+    "UB", "UR", "UF", "UL", "BL", "BR", "FR", "FL", "DF", "DR", "DB", "DL",
+];
+
+/// The kinds of pieces that can be cycled.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PieceType {
+    Corner,
+    Edge,
+}
+
+impl PieceType {
+    fn get_names(&self) -> &'static [&'static str] {
+        match self {
+            PieceType::Corner => &CORNER_NAMES,
+            PieceType::Edge => &EDGE_NAMES,
+        }
+    }
+
+    pub(crate) fn get_num_orientations(&self) -> u8 {
+        match self {
+            PieceType::Corner => 3,
+            PieceType::Edge => 2,
+        }
+    }
+
+    /// Returns the faces of the stickers of a position, in the order
+    /// used for the orientation of the pieces.
+    fn get_sticker_faces(&self, pos: usize) -> &'static [Face] {
+        match self {
+            PieceType::Corner => &CORNER_COLORS[pos],
+            PieceType::Edge => &EDGE_COLORS[pos],
+        }
+    }
+}
+
+/// A cycle of corners or edges.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct PieceCycle {
+    pub piece_type: PieceType,
+    /// The positions visited by the cycle, each paired with the sticker of
+    /// the position the traced sticker is on.
+    ///
+    /// Stickers are numbered from the U or D sticker (the F or B sticker
+    /// for the middle layer edges), going clockwise for corners.
+    pub stickers: Vec<(u8, u8)>,
+    /// The net twist of the corners (1 for clockwise, 2 for counter-clockwise),
+    /// or the net flip of the edges, after going once around the cycle.
+    pub twist: u8,
+}
+
+impl PieceCycle {
+    /// Returns the names of the positions visited by this cycle, with
+    /// their letters ordered to show the orientation of the pieces.
+    pub fn get_names(&self) -> Vec<String> {
+        self.stickers
+            .iter()
+            .map(|&(pos, sticker)| {
+                let name = self.piece_type.get_names()[pos as usize];
+                let face = self.piece_type.get_sticker_faces(pos as usize)[sticker as usize];
+                let start = name.find(&face.to_string()).unwrap();
+                format!("{}{}", &name[start..], &name[..start])
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for PieceCycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let suffix = match self.twist {
+            0 => "",
+            1 => "+",
+            _ => "-",
+        };
+        write!(f, "({}){}", self.get_names().join(" "), suffix)
+    }
+}
+
+/// The reasons why a string may fail to be read as cycle notation.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum CycleNotationError {
+    /// A cycle is missing its opening or closing parenthesis.
+    UnbalancedParentheses,
+    /// A cycle contains no pieces.
+    EmptyCycle,
+    /// A name does not denote any corner or edge position.
+    UnknownPiece(String),
+    /// A cycle contains both corners and edges.
+    MixedPieceTypes,
+    /// A position appears more than once.
+    RepeatedPiece(String),
+    /// A cycle of edges is followed by `-`.
+    InvalidTwist,
+    /// A character that is not part of the notation.
+    UnexpectedCharacter(char),
+}
+
+impl std::fmt::Display for CycleNotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CycleNotationError::UnbalancedParentheses => write!(f, "unbalanced parentheses"),
+            CycleNotationError::EmptyCycle => write!(f, "a cycle contains no pieces"),
+            CycleNotationError::UnknownPiece(name) => write!(f, "unknown piece '{}'", name),
+            CycleNotationError::MixedPieceTypes => {
+                write!(f, "a cycle contains both corners and edges")
+            }
+            CycleNotationError::RepeatedPiece(name) => {
+                write!(f, "piece '{}' appears more than once", name)
+            }
+            CycleNotationError::InvalidTwist => write!(f, "edges can only be flipped with '+'"),
+            CycleNotationError::UnexpectedCharacter(c) => {
+                write!(f, "unexpected character '{}'", c)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CycleNotationError {}
+
+/// Finds the position and sticker denoted by a (possibly reordered) name.
+pub(crate) fn parse_piece(name: &str) -> Result<(PieceType, u8, u8), CycleNotationError> {
+    let unknown = || CycleNotationError::UnknownPiece(name.to_string());
+    let mut letters: Vec<char> = name.chars().collect();
+    let first = ColorScheme::default()
+        .get_face(letters[0])
+        .ok_or_else(unknown)?;
+    letters.sort_unstable();
+    for piece_type in [PieceType::Corner, PieceType::Edge].iter() {
+        for (pos, candidate) in piece_type.get_names().iter().enumerate() {
+            let mut candidate_letters: Vec<char> = candidate.chars().collect();
+            candidate_letters.sort_unstable();
+            if candidate_letters == letters {
+                let sticker = piece_type
+                    .get_sticker_faces(pos)
+                    .iter()
+                    .position(|&f| f == first)
+                    .unwrap();
+                return Ok((*piece_type, pos as u8, sticker as u8));
+            }
+        }
+    }
+    Err(unknown())
+}
+
+/// Reads a list of cycles written in cycle notation.
+///
+/// Names may have their letters in any order, with the first letter
+/// denoting the traced sticker. Pieces within a cycle may be separated
+/// by spaces or commas.
+pub fn parse_cycle_notation(notation: &str) -> Result<Vec<PieceCycle>, CycleNotationError> {
+    let mut cycles = vec![];
+    let mut seen: Vec<(PieceType, u8)> = vec![];
+    let mut chars = notation.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => {}
+            ')' => return Err(CycleNotationError::UnbalancedParentheses),
+            c if c.is_whitespace() => continue,
+            c => return Err(CycleNotationError::UnexpectedCharacter(c)),
+        }
+        let mut body = String::new();
+        loop {
+            match chars.next() {
+                Some(')') => break,
+                Some('(') | None => return Err(CycleNotationError::UnbalancedParentheses),
+                Some(c) => body.push(c),
+            }
+        }
+        let mut piece_type = None;
+        let mut stickers = vec![];
+        for name in body
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|s| !s.is_empty())
+        {
+            let (kind, pos, sticker) = parse_piece(name)?;
+            if piece_type.get_or_insert(kind) != &kind {
+                return Err(CycleNotationError::MixedPieceTypes);
+            }
+            if seen.contains(&(kind, pos)) {
+                return Err(CycleNotationError::RepeatedPiece(name.to_string()));
+            }
+            seen.push((kind, pos));
+            stickers.push((pos, sticker));
+        }
+        let piece_type = piece_type.ok_or(CycleNotationError::EmptyCycle)?;
+        let twist = match chars.peek() {
+            Some('+') => 1,
+            Some('-') if piece_type == PieceType::Corner => 2,
+            Some('-') => return Err(CycleNotationError::InvalidTwist),
+            _ => 0,
+        };
+        if twist != 0 {
+            chars.next();
+        }
+        cycles.push(PieceCycle {
+            piece_type,
+            stickers,
+            twist,
+        });
+    }
+    Ok(cycles)
+}
+
+/// Breaks a permutation of pieces with orientations into cycles,
+/// leaving out pieces which are solved.
+fn get_piece_cycles(perm: &[u8], ori: &[u8], piece_type: PieceType) -> Vec<PieceCycle> {
+    let n = piece_type.get_num_orientations();
+    // the position each piece has moved to
+    let mut dest = vec![0; perm.len()];
+    for (pos, &piece) in perm.iter().enumerate() {
+        dest[piece as usize] = pos;
+    }
+    let mut visited = vec![false; perm.len()];
+    let mut cycles = vec![];
+    for start in 0..perm.len() {
+        if visited[start] {
+            continue;
+        }
+        let mut stickers = vec![];
+        let mut pos = start;
+        let mut sticker = 0;
+        loop {
+            visited[pos] = true;
+            stickers.push((pos as u8, sticker));
+            pos = dest[pos];
+            sticker = (sticker + ori[pos]) % n;
+            if pos == start {
+                break;
+            }
+        }
+        if stickers.len() > 1 || sticker != 0 {
+            cycles.push(PieceCycle {
+                piece_type,
+                stickers,
+                twist: sticker,
+            });
+        }
+    }
+    cycles
+}
+
+impl CubeState {
+    /// Breaks this state into cycles of corners followed by cycles of
+    /// edges, each starting from its earliest position in the order used
+    /// by `CubeState`. Twisted corners and flipped edges which are in their
+    /// home positions are given as cycles of length one.
+    pub fn get_cycles(&self) -> Vec<PieceCycle> {
+        let co = self.co.map(|x| (x + 3) as u8 % 3);
+        let eo = self.eo.map(|x| x as u8);
+        let mut cycles = get_piece_cycles(&self.cp, &co, PieceType::Corner);
+        cycles.extend(get_piece_cycles(&self.ep, &eo, PieceType::Edge));
+        cycles
+    }
+
+    /// Returns the cycle notation of this state, which is empty
+    /// if all the corners and edges are solved.
+    pub fn to_cycle_notation(&self) -> String {
+        self.get_cycles()
+            .iter()
+            .map(|cycle| cycle.to_string())
+            .collect()
+    }
+
+    /// Constructs the state described by a list of disjoint cycles,
+    /// with the centers in their home positions.
+    ///
+    /// The state is not validated, so that any arrangement of the pieces
+    /// can be described; use `validate` to check that it is solvable.
+    pub fn from_cycles(cycles: &[PieceCycle]) -> Self {
+        let mut state = CubeState::default();
+        for cycle in cycles {
+            let n = cycle.piece_type.get_num_orientations();
+            let len = cycle.stickers.len();
+            for i in 0..len {
+                let (from, from_sticker) = cycle.stickers[i];
+                let (to, to_sticker) = cycle.stickers[(i + 1) % len];
+                // the cycle's twist is added when returning to the start
+                let to_sticker = if i == len - 1 {
+                    to_sticker + cycle.twist
+                } else {
+                    to_sticker
+                };
+                let ori = (to_sticker + n - from_sticker) % n;
+                match cycle.piece_type {
+                    PieceType::Corner => {
+                        state.cp[to as usize] = from;
+                        state.co[to as usize] = if ori == 2 { -1 } else { ori as i8 };
+                    }
+                    PieceType::Edge => {
+                        state.ep[to as usize] = from;
+                        state.eo[to as usize] = ori as i8;
+                    }
+                }
+            }
+        }
+        state
+    }
+
+    /// Constructs the state described by a string in cycle notation,
+    /// with the centers in their home positions.
+    ///
+    /// The state is not validated, so that any arrangement of the pieces
+    /// can be described; use `validate` to check that it is solvable.
+    pub fn from_cycle_notation(notation: &str) -> Result<Self, CycleNotationError> {
+        Ok(Self::from_cycles(&parse_cycle_notation(notation)?))
+    }
+}
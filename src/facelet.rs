@@ -0,0 +1,335 @@
+//! A module for converting between the piece representation of the
+//! Rubik's Cube and its 54-sticker (facelet) representation.
+//!
+//! The facelet representation follows the format used by Kociemba's
+//! solver, which most scanners and GUIs also use. The stickers are listed
+//! face by face in the order U, R, F, D, L, B, and each face is read
+//! left-to-right, top-to-bottom:
+//!
+//! ```text
+//!              |************|
+//!              |*U1**U2**U3*|
+//!              |************|
+//!              |*U4**U5**U6*|
+//!              |************|
+//!              |*U7**U8**U9*|
+//!              |************|
+//! |************|************|************|************|
+//! |*L1**L2**L3*|*F1**F2**F3*|*R1**R2**R3*|*B1**B2**B3*|
+//! |************|************|************|************|
+//! |*L4**L5**L6*|*F4**F5**F6*|*R4**R5**R6*|*B4**B5**B6*|
+//! |************|************|************|************|
+//! |*L7**L8**L9*|*F7**F8**F9*|*R7**R8**R9*|*B7**B8**B9*|
+//! |************|************|************|************|
+//!              |************|
+//!              |*D1**D2**D3*|
+//!              |************|
+//!              |*D4**D5**D6*|
+//!              |************|
+//!              |*D7**D8**D9*|
+//!              |************|
+//! ```
+//!
+//! The solved state is thus `UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB`.
+
+use crate::cube::*;
+
+/// An enum for the faces of the Rubik's Cube, in facelet order.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum Face {
+    U,
+    R,
+    F,
+    D,
+    L,
+    B,
+}
+
+/// All faces of the Rubik's Cube, in facelet order.
+pub const ALL_FACES: [Face; 6] = [Face::U, Face::R, Face::F, Face::D, Face::L, Face::B];
+
+impl std::fmt::Display for Face {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A mapping from faces to the characters used to denote their stickers.
+///
+/// The `i`-th character is used for the stickers of the `i`-th face in
+/// `ALL_FACES`. The default scheme simply uses the face names, as in
+/// Kociemba's format.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ColorScheme(pub [char; 6]);
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        ColorScheme(['U', 'R', 'F', 'D', 'L', 'B'])
+    }
+}
+
+impl ColorScheme {
+    /// A scheme using the initials of the standard Western colors
+    /// (white top, green front).
+    pub fn western() -> Self {
+        ColorScheme(['W', 'R', 'G', 'Y', 'O', 'B'])
+    }
+
+    /// Reads the color scheme off the center stickers of a facelet string,
+    /// ignoring whitespace as `CubeState::from_facelet_string` does.
+    pub fn from_centers(facelets: &str) -> Result<Self, FaceletError> {
+        let chars = get_stickers(facelets)?;
+        let mut colors = [' '; 6];
+        for (i, color) in colors.iter_mut().enumerate() {
+            *color = chars[9 * i + 4];
+        }
+        for i in 0..6 {
+            if colors[(i + 1)..].contains(&colors[i]) {
+                return Err(FaceletError::DuplicateCenter(colors[i]));
+            }
+        }
+        Ok(ColorScheme(colors))
+    }
+
+    /// Returns the character used for the stickers of a face.
+    pub fn get_color(&self, face: Face) -> char {
+        self.0[face as usize]
+    }
+
+    /// Returns the face whose stickers are denoted by a character, if any.
+    pub fn get_face(&self, color: char) -> Option<Face> {
+        self.0
+            .iter()
+            .position(|&c| c == color)
+            .map(|i| ALL_FACES[i])
+    }
+}
+
+/// Returns the 54 stickers of a facelet string, skipping whitespace.
+fn get_stickers(facelets: &str) -> Result<Vec<char>, FaceletError> {
+    let chars: Vec<char> = facelets.chars().filter(|c| !c.is_whitespace()).collect();
+    if chars.len() != 54 {
+        return Err(FaceletError::WrongLength(chars.len()));
+    }
+    Ok(chars)
+}
+
+/// The reasons why a facelet representation may fail to describe
+/// a configuration of the Rubik's Cube.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum FaceletError {
+    /// The facelet string does not have exactly 54 stickers.
+    WrongLength(usize),
+    /// A sticker has a color that is not part of the color scheme.
+    UnknownColor(char),
+    /// Two centers have the same color.
+    DuplicateCenter(char),
+    /// A face does not have exactly 9 stickers of its color.
+    WrongStickerCount(Face, usize),
+    /// The stickers on the corner at the given position do not
+    /// form any corner of the Rubik's Cube.
+    InvalidCorner(usize),
+    /// The stickers on the edge at the given position do not
+    /// form any edge of the Rubik's Cube.
+    InvalidEdge(usize),
+    /// The stickers form valid pieces, but not a solvable state.
+    InvalidState(InvalidStateError),
+}
+
+impl std::fmt::Display for FaceletError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FaceletError::WrongLength(n) => write!(f, "expected 54 stickers, found {}", n),
+            FaceletError::UnknownColor(c) => write!(f, "unknown sticker color '{}'", c),
+            FaceletError::DuplicateCenter(c) => {
+                write!(f, "more than one center has color '{}'", c)
+            }
+            FaceletError::WrongStickerCount(face, n) => {
+                write!(f, "expected 9 stickers of the {} color, found {}", face, n)
+            }
+            FaceletError::InvalidCorner(i) => {
+                write!(f, "corner {} has an impossible color combination", i)
+            }
+            FaceletError::InvalidEdge(i) => {
+                write!(f, "edge {} has an impossible color combination", i)
+            }
+            FaceletError::InvalidState(e) => write!(f, "unsolvable state: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FaceletError {}
+
+impl From<InvalidStateError> for FaceletError {
+    fn from(e: InvalidStateError) -> Self {
+        FaceletError::InvalidState(e)
+    }
+}
+
+/// The facelet indices of each corner position, listed clockwise
+/// starting from the U or D sticker.
+///
+/// The corners are in the order UBL UBR UFR UFL DFL DFR DBR DBL.
+pub(crate) const CORNER_FACELETS: [[usize; 3]; 8] = [
+    [0, 36, 47],
+    [2, 45, 11],
+    [8, 9, 20],
+    [6, 18, 38],
+    [27, 44, 24],
+    [29, 26, 15],
+    [35, 17, 51],
+    [33, 53, 42],
+];
+
+/// The colors of each corner piece, in the same order as `CORNER_FACELETS`.
+pub(crate) const CORNER_COLORS: [[Face; 3]; 8] = [
+    [Face::U, Face::L, Face::B],
+    [Face::U, Face::B, Face::R],
+    [Face::U, Face::R, Face::F],
+    [Face::U, Face::F, Face::L],
+    [Face::D, Face::L, Face::F],
+    [Face::D, Face::F, Face::R],
+    [Face::D, Face::R, Face::B],
+    [Face::D, Face::B, Face::L],
+];
+
+/// The facelet indices of each edge position, starting from the
+/// U or D sticker (or the F or B sticker for the middle layer).
+///
+/// The edges are in the order UB UR UF UL BL BR FR FL DF DR DB DL.
+pub(crate) const EDGE_FACELETS: [[usize; 2]; 12] = [
+    [1, 46],
+    [5, 10],
+    [7, 19],
+    [3, 37],
+    [50, 39],
+    [48, 14],
+    [23, 12],
+    [21, 41],
+    [28, 25],
+    [32, 16],
+    [34, 52],
+    [30, 43],
+];
+
+/// The faces of the centers, in the order used by `CubeState`.
+pub(crate) const CENTER_FACES: [Face; 6] = [Face::U, Face::D, Face::L, Face::R, Face::F, Face::B];
+
+/// The colors of each edge piece, in the same order as `EDGE_FACELETS`.
+pub(crate) const EDGE_COLORS: [[Face; 2]; 12] = [
+    [Face::U, Face::B],
+    [Face::U, Face::R],
+    [Face::U, Face::F],
+    [Face::U, Face::L],
+    [Face::B, Face::L],
+    [Face::B, Face::R],
+    [Face::F, Face::R],
+    [Face::F, Face::L],
+    [Face::D, Face::F],
+    [Face::D, Face::R],
+    [Face::D, Face::B],
+    [Face::D, Face::L],
+];
+
+/// Returns the corner piece and orientation shown by the stickers of a
+/// corner position, listed as in `CORNER_FACELETS`, if they form a corner.
+pub(crate) fn get_corner_of_colors(colors: [Face; 3]) -> Option<(u8, i8)> {
+    let ori = colors.iter().position(|&c| c == Face::U || c == Face::D)?;
+    let piece = CORNER_COLORS
+        .iter()
+        .position(|c| (0..3).all(|k| c[k] == colors[(k + ori) % 3]))?;
+    Some((piece as u8, if ori == 2 { -1 } else { ori as i8 }))
+}
+
+/// Returns the edge piece and orientation shown by the stickers of an
+/// edge position, listed as in `EDGE_FACELETS`, if they form an edge.
+pub(crate) fn get_edge_of_colors(colors: [Face; 2]) -> Option<(u8, i8)> {
+    EDGE_COLORS.iter().enumerate().find_map(|(e, c)| {
+        if *c == colors {
+            Some((e as u8, 0))
+        } else if c[0] == colors[1] && c[1] == colors[0] {
+            Some((e as u8, 1))
+        } else {
+            None
+        }
+    })
+}
+
+impl CubeState {
+    /// Returns the 54 stickers of this state, in facelet order.
+    pub fn to_facelets(&self) -> [Face; 54] {
+        let mut facelets = [Face::U; 54];
+        for (pos, face) in CENTER_FACES.iter().enumerate() {
+            facelets[9 * (*face as usize) + 4] = CENTER_FACES[self.centers[pos] as usize];
+        }
+        for pos in 0..8 {
+            let piece = self.cp[pos] as usize;
+            let ori = (self.co[pos] + 3) as usize % 3;
+            for k in 0..3 {
+                facelets[CORNER_FACELETS[pos][(k + ori) % 3]] = CORNER_COLORS[piece][k];
+            }
+        }
+        for pos in 0..12 {
+            let piece = self.ep[pos] as usize;
+            let ori = self.eo[pos] as usize;
+            for k in 0..2 {
+                facelets[EDGE_FACELETS[pos][(k + ori) % 2]] = EDGE_COLORS[piece][k];
+            }
+        }
+        facelets
+    }
+
+    /// Constructs a state from its 54 stickers, given in facelet order.
+    ///
+    /// Returns an error if the stickers do not describe a solvable
+    /// configuration of the Rubik's Cube.
+    pub fn from_facelets(facelets: &[Face; 54]) -> Result<Self, FaceletError> {
+        for face in ALL_FACES.iter() {
+            let count = facelets.iter().filter(|f| *f == face).count();
+            if count != 9 {
+                return Err(FaceletError::WrongStickerCount(*face, count));
+            }
+        }
+
+        let mut state = CubeState::default();
+        for (pos, face) in CENTER_FACES.iter().enumerate() {
+            let center = facelets[9 * (*face as usize) + 4];
+            state.centers[pos] = CENTER_FACES.iter().position(|&f| f == center).unwrap() as u8;
+        }
+        for (pos, corner) in CORNER_FACELETS.iter().enumerate() {
+            let (piece, ori) = get_corner_of_colors(corner.map(|i| facelets[i]))
+                .ok_or(FaceletError::InvalidCorner(pos))?;
+            state.cp[pos] = piece;
+            state.co[pos] = ori;
+        }
+        for (pos, edge) in EDGE_FACELETS.iter().enumerate() {
+            let (piece, ori) = get_edge_of_colors(edge.map(|i| facelets[i]))
+                .ok_or(FaceletError::InvalidEdge(pos))?;
+            state.ep[pos] = piece;
+            state.eo[pos] = ori;
+        }
+        state.validate()?;
+        Ok(state)
+    }
+
+    /// Returns the facelet string of this state, using the given color scheme.
+    pub fn to_facelet_string(&self, scheme: &ColorScheme) -> String {
+        self.to_facelets()
+            .iter()
+            .map(|f| scheme.get_color(*f))
+            .collect()
+    }
+
+    /// Constructs a state from a facelet string, using the given color scheme.
+    ///
+    /// Whitespace in the string is ignored, so faces may be separated
+    /// for readability.
+    pub fn from_facelet_string(facelets: &str, scheme: &ColorScheme) -> Result<Self, FaceletError> {
+        let chars = get_stickers(facelets)?;
+        let mut faces = [Face::U; 54];
+        for (face, c) in faces.iter_mut().zip(chars) {
+            *face = scheme.get_face(c).ok_or(FaceletError::UnknownColor(c))?;
+        }
+        Self::from_facelets(&faces)
+    }
+}
@@ -0,0 +1,364 @@
+//! A module for recognizing the cases of the last layer.
+//!
+//! Once the first two layers (F2L) are solved, the last layer is usually
+//! solved by algorithms chosen from one of several sets of cases:
+//!
+//! - OLL orients the last layer, with cases numbered 1 to 57
+//! - PLL permutes an oriented last layer, with cases named by letter,
+//!   e.g. `T`, `Ja` or `Ub`
+//! - COLL solves the corners of a last layer whose edges are oriented
+//! - ZBLL solves a whole last layer whose edges are oriented
+//!
+//! A case is the same whichever side of the cube it is looked at from, so
+//! recognition is invariant under turns of the U face before and after
+//! the case (the AUF, for "adjust U face"), as well as under y rotations
+//! of the whole cube. Along with the case, the AUF needed before and after
+//! its algorithm is returned.
+//!
+//! The OLL and PLL cases are defined by their first algorithm in the
+//! built-in database of `algdb`. The COLL and ZBLL cases are named after
+//! the standard shape of their corner orientation (H, Pi, U, T, L, S, AS
+//! or O), followed by `#` and an opaque index within the shape, e.g. `H#1`.
+//! The index is fixed, but it does not follow any published numbering, so
+//! `H#1` is not necessarily the case called H1 on a COLL sheet. The ZBLL
+//! cases whose corners are oriented are named after their PLL instead.
+//! For these two sets, the AUF is relative to the state returned by
+//! `get_case_state`.
+//!
+//! ```
+//! use rusty_rubik::cube::*;
+//! use rusty_rubik::lastlayer::*;
+//! use rusty_rubik::parser::parse_scramble;
+//!
+//! fn main() {
+//!     // a T perm, followed by a U turn
+//!     let seq = MoveSequence(parse_scramble("R U R' U' R' F R2 U' R' U' R U R' F' U").unwrap());
+//!     let state = CubeState::default().apply_move_instances(&seq);
+//!
+//!     let pll = recognize(&state, CaseSet::PLL).unwrap();
+//!     assert_eq!(pll.name, "T");
+//!     assert_eq!(pll.pre_auf, Some(Direction::Prime));
+//!     assert_eq!(pll.post_auf, None);
+//! }
+//! ```
+
+use crate::algdb::{AlgDatabase, AlgSet};
+use crate::cube::*;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+/// The sets of last layer cases that can be recognized.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum CaseSet {
+    OLL,
+    PLL,
+    COLL,
+    ZBLL,
+}
+
+/// A recognized last layer case.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct LLCase {
+    pub set: CaseSet,
+    /// The name of the case, e.g. `21` for OLL, `Ja` for PLL or `H#1` for
+    /// COLL, or `skip` if there is nothing to solve besides the AUF. The
+    /// index after `#` is opaque; see the module documentation.
+    pub name: String,
+    /// The algorithm defining the case, for OLL and PLL.
+    pub algorithm: Option<MoveSequence>,
+    /// The turn of the U face to make before the algorithm.
+    pub pre_auf: Option<Direction>,
+    /// The turn of the U face to make after the algorithm. This is always
+    /// `None` for OLL, which leaves the pieces unpermuted.
+    pub post_auf: Option<Direction>,
+}
+
+/// The OLL cases that orient the edges, with the shape their
+/// corners are named after in COLL and ZBLL.
+const CORNER_SHAPES: [(&str, &str); 8] = [
+    ("21", "H"),
+    ("22", "Pi"),
+    ("23", "U"),
+    ("24", "T"),
+    ("25", "L"),
+    ("26", "AS"),
+    ("27", "S"),
+    ("skip", "O"),
+];
+
+/// The last layer pieces of a state that a case set depends on: the
+/// permutation and orientation of the corners and of the edges.
+type CaseKey = ([u8; 4], [i8; 4], [u8; 4], [i8; 4]);
+
+fn get_key(state: &CubeState, set: CaseSet) -> CaseKey {
+    let mut key = ([0; 4], [0; 4], [0; 4], [0; 4]);
+    if set != CaseSet::OLL {
+        key.0.copy_from_slice(&state.cp[..4]);
+    }
+    if set != CaseSet::PLL {
+        key.1.copy_from_slice(&state.co[..4]);
+    }
+    if set == CaseSet::PLL || set == CaseSet::ZBLL {
+        key.2.copy_from_slice(&state.ep[..4]);
+    }
+    if set == CaseSet::OLL {
+        key.3.copy_from_slice(&state.eo[..4]);
+    }
+    key
+}
+
+/// The numbers of clockwise U turns, in the order they are preferred as AUFs.
+pub(crate) const AUF_TURNS: [u8; 4] = [0, 1, 3, 2];
+
+pub(crate) fn get_u_turn(turns: u8) -> CubeState {
+    match Direction::from_num_turns(turns) {
+        Some(dir) => {
+            CubeState::default().apply_move_instance(&MoveInstance::new(BaseMoveToken::U, dir))
+        }
+        None => CubeState::default(),
+    }
+}
+
+/// The cases of a set, with a lookup from the key of every state
+/// of each case to the case and the AUFs that solve it.
+struct CaseTable {
+    set: CaseSet,
+    cases: Vec<(String, Option<MoveSequence>, CubeState)>,
+    lookup: HashMap<CaseKey, (usize, u8, u8)>,
+}
+
+impl CaseTable {
+    /// Builds a table from the name, algorithm and a state of each case.
+    fn new(set: CaseSet, cases: Vec<(String, Option<MoveSequence>, CubeState)>) -> Self {
+        let mut lookup = HashMap::new();
+        for (i, (_, _, state)) in cases.iter().enumerate() {
+            // the state U^a S U^b is solved by U^-b, then the algorithm, then U^-a
+            for &pre in AUF_TURNS.iter() {
+                for &post in AUF_TURNS.iter() {
+                    let variant = get_u_turn(post)
+                        .compose(state)
+                        .compose(&get_u_turn((4 - pre) % 4));
+                    lookup
+                        .entry(get_key(&variant, set))
+                        .or_insert((i, pre, (4 - post) % 4));
+                }
+            }
+        }
+        CaseTable { set, cases, lookup }
+    }
+
+    /// Builds a table from the first algorithm of each case of a set
+    /// in the built-in database.
+    fn from_database(set: CaseSet, alg_set: AlgSet) -> Self {
+        let mut cases = vec![(
+            String::from("skip"),
+            Some(MoveSequence(vec![])),
+            CubeState::default(),
+        )];
+        let db = AlgDatabase::builtin();
+        for case in db.get_cases().iter().filter(|case| case.set == alg_set) {
+            let alg = case.algorithms[0].clone();
+            cases.push((case.name.clone(), Some(alg), case.get_state().clone()));
+        }
+        CaseTable::new(set, cases)
+    }
+
+    /// Builds a table whose cases are the classes of the given states
+    /// under the AUFs, named after the shape of their corners.
+    fn from_states(set: CaseSet, states: Vec<CubeState>) -> Self {
+        let mut classes: HashMap<CaseKey, CubeState> = HashMap::new();
+        for state in states {
+            let (key, rep) = get_canonical_form(&state, set);
+            classes.entry(key).or_insert(rep);
+        }
+        let mut reps: Vec<(CaseKey, CubeState)> = classes.into_iter().collect();
+        reps.sort_by_key(|(key, _)| *key);
+
+        let (solved_key, solved) = get_canonical_form(&CubeState::default(), set);
+        let mut cases = vec![(String::from("skip"), None, solved)];
+        for (oll_name, shape) in CORNER_SHAPES.iter() {
+            let mut count = 0;
+            for (key, state) in reps.iter() {
+                if *key == solved_key || OLL_TABLE.recognize(state).unwrap().name != *oll_name {
+                    continue;
+                }
+                let name = if set == CaseSet::ZBLL && *shape == "O" {
+                    PLL_TABLE.recognize(state).unwrap().name
+                } else {
+                    count += 1;
+                    format!("{}#{}", shape, count)
+                };
+                cases.push((name, None, state.clone()));
+            }
+        }
+        CaseTable::new(set, cases)
+    }
+
+    /// Recognizes a normalized state with the first two layers solved.
+    fn recognize(&self, state: &CubeState) -> Option<LLCase> {
+        let (i, pre, post) = self.lookup.get(&get_key(state, self.set))?;
+        let (name, algorithm, _) = &self.cases[*i];
+        Some(LLCase {
+            set: self.set,
+            name: name.clone(),
+            algorithm: algorithm.clone(),
+            pre_auf: Direction::from_num_turns(*pre),
+            post_auf: Direction::from_num_turns(*post),
+        })
+    }
+}
+
+/// Returns the smallest key of the states of a case,
+/// along with the state it belongs to.
+fn get_canonical_form(state: &CubeState, set: CaseSet) -> (CaseKey, CubeState) {
+    let mut best: Option<(CaseKey, CubeState)> = None;
+    for &pre in AUF_TURNS.iter() {
+        for &post in AUF_TURNS.iter() {
+            let variant = get_u_turn(pre).compose(state).compose(&get_u_turn(post));
+            let key = get_key(&variant, set);
+            if best.as_ref().is_none_or(|(best_key, _)| key < *best_key) {
+                best = Some((key, variant));
+            }
+        }
+    }
+    best.unwrap()
+}
+
+/// Returns every state whose first two layers are solved and whose
+/// last layer edges are oriented. If `with_edges` is false, the last
+/// layer edges are solved as well, except that UB and UR are swapped
+/// when the corners are an odd permutation, so that every state is solvable.
+fn get_oriented_edge_states(with_edges: bool) -> Vec<CubeState> {
+    let perms: Vec<[u8; 4]> = (0..24)
+        .map(|i| get_permutation_of_index(i, 4))
+        .map(|perm| [perm[0], perm[1], perm[2], perm[3]])
+        .collect();
+    let parity = |perm: &[u8; 4]| {
+        (0..4)
+            .flat_map(|i| (i + 1..4).map(move |j| (i, j)))
+            .filter(|&(i, j)| perm[i] > perm[j])
+            .count()
+            % 2
+    };
+    let mut states = vec![];
+    for cp in perms.iter() {
+        for twist in 0..27 {
+            let mut co = [0; 8];
+            let mut t = twist;
+            for ori in co.iter_mut().take(3) {
+                *ori = (t % 3) as i8 - 1;
+                t /= 3;
+            }
+            co[3] = match (-(co[0] + co[1] + co[2])).rem_euclid(3) {
+                2 => -1,
+                x => x,
+            };
+            for ep in perms.iter() {
+                let solved_edges = match parity(cp) {
+                    0 => [0, 1, 2, 3],
+                    _ => [1, 0, 2, 3],
+                };
+                if (with_edges && parity(ep) != parity(cp)) || (!with_edges && *ep != solved_edges)
+                {
+                    continue;
+                }
+                let mut state = CubeState::default();
+                state.cp[..4].copy_from_slice(cp);
+                state.co = co;
+                state.ep[..4].copy_from_slice(ep);
+                states.push(state);
+            }
+        }
+    }
+    states
+}
+
+lazy_static! {
+    static ref OLL_TABLE: CaseTable = CaseTable::from_database(CaseSet::OLL, AlgSet::OLL);
+    static ref PLL_TABLE: CaseTable = CaseTable::from_database(CaseSet::PLL, AlgSet::PLL);
+    static ref COLL_TABLE: CaseTable =
+        CaseTable::from_states(CaseSet::COLL, get_oriented_edge_states(false));
+    static ref ZBLL_TABLE: CaseTable =
+        CaseTable::from_states(CaseSet::ZBLL, get_oriented_edge_states(true));
+}
+
+fn get_table(set: CaseSet) -> &'static CaseTable {
+    match set {
+        CaseSet::OLL => &OLL_TABLE,
+        CaseSet::PLL => &PLL_TABLE,
+        CaseSet::COLL => &COLL_TABLE,
+        CaseSet::ZBLL => &ZBLL_TABLE,
+    }
+}
+
+/// Returns the state as seen from the current position of its centers,
+/// which may be turned by a y rotation, or `None` if they are turned
+/// otherwise. Moves made on the result are the same as on the state.
+///
+/// If the state is reached by the moves $A$ and its centers by the
+/// rotation $Y$, the result is the state reached by $Y^{-1}A$.
+pub(crate) fn normalize(state: &CubeState) -> Option<CubeState> {
+    let y = MoveInstance::new(BaseMoveToken::Y, Direction::Normal);
+    let mut rotation = CubeState::default();
+    for _ in 0..4 {
+        let result = rotation.compose(state);
+        if result.centers == CubeState::default().centers {
+            return Some(result);
+        }
+        rotation = rotation.apply_move_instance(&y);
+    }
+    None
+}
+
+/// Returns `true` if the first two layers of a state are solved, that is,
+/// if the pieces of the D and E layers are solved relative to the centers,
+/// which may be turned by a y rotation.
+pub fn is_f2l_solved(state: &CubeState) -> bool {
+    match normalize(state) {
+        Some(state) => {
+            (4..8).all(|i| state.cp[i] == i as u8 && state.co[i] == 0)
+                && (4..12).all(|i| state.ep[i] == i as u8 && state.eo[i] == 0)
+        }
+        None => false,
+    }
+}
+
+/// Recognizes the last layer case of a state in a case set.
+///
+/// Returns `None` if the first two layers are not solved, or if the
+/// last layer is not in the set: the PLL cases require an oriented last
+/// layer, and the COLL and ZBLL cases require oriented edges.
+pub fn recognize(state: &CubeState, set: CaseSet) -> Option<LLCase> {
+    if !is_f2l_solved(state) {
+        return None;
+    }
+    let state = normalize(state)?;
+    let oriented = match set {
+        CaseSet::OLL => true,
+        CaseSet::PLL => state.co[..4] == [0; 4] && state.eo[..4] == [0; 4],
+        CaseSet::COLL | CaseSet::ZBLL => state.eo[..4] == [0; 4],
+    };
+    if !oriented {
+        return None;
+    }
+    get_table(set).recognize(&state)
+}
+
+/// Returns the names of the cases of a set, in order, including `skip`.
+pub fn get_case_names(set: CaseSet) -> Vec<String> {
+    let cases = &get_table(set).cases;
+    cases.iter().map(|(name, _, _)| name.clone()).collect()
+}
+
+/// Returns a state of the case with the given name, solved by its
+/// algorithm without any AUF.
+///
+/// For COLL, the last layer edges of the state are solved, up to a
+/// swap of two of them when the corners need one.
+pub fn get_case_state(set: CaseSet, name: &str) -> Option<CubeState> {
+    let cases = &get_table(set).cases;
+    cases
+        .iter()
+        .find(|(case_name, _, _)| case_name == name)
+        .map(|(_, _, state)| state.clone())
+}
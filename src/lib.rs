@@ -3,17 +3,39 @@
 //! The crate includes consists of two separate compartments:
 //!
 //! - An **executable** that allows you to instantly search for a solution to a
-//! configuration of the Rubik's Cube.
+//!   configuration of the Rubik's Cube.
 //!
 //! - A **library** that provides utility functions for solver methods, pruning table
-//! generation, and an API for Rubik's Cube structure.
+//!   generation, and an API for Rubik's Cube structure.
 //!
+//! Enabling the `serde` feature implements `Serialize` and `Deserialize`
+//! for the states and moves of the cube, and `Serialize` for the reports
+//! of `reconstruction`.
 //!
 
-// pub mod cube;
-// pub mod parser;
-// pub mod pruning;
-// pub mod solver;
+pub mod algdb;
+pub mod algorithm;
+pub mod bld;
+pub mod coord;
+pub mod cube;
+pub mod cycle;
+pub mod facelet;
+pub mod lastlayer;
+pub mod masked;
+pub mod metric;
+pub mod packed;
+pub mod parser;
+pub mod pruning;
+pub mod reconstruction;
+pub mod render;
+pub mod scrambler;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod solver;
+pub mod steps;
+pub mod svg;
+pub mod symmetry;
+pub mod twophase;
 
 pub mod puzzle;
 
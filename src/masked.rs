@@ -0,0 +1,265 @@
+//! A module for partial states of the Rubik's Cube, where only some
+//! pieces matter.
+//!
+//! A `MaskedState` is what can be seen of a state through a `PieceMask`,
+//! which gives each piece a label and says whether its orientation is
+//! seen. Pieces sharing a label cannot be told apart, and pieces without
+//! a label are wildcards, so that for example:
+//!
+//! - the cross only labels the edges of the D face
+//! - OLL gives all pieces of the last layer the same label, ignoring
+//!   their permutation but not their orientation
+//! - domino reduction labels every corner the same, and the edges by
+//!   whether they belong in the E slice
+//!
+//! Two masked states are equal if they look the same, so that they can be
+//! hashed and searched like full states, and a partial goal is reached
+//! once a masked state equals the solved state under its mask. The
+//! centers are not part of a masked state.
+//!
+//! ```
+//! use rusty_rubik::cube::*;
+//! use rusty_rubik::masked::*;
+//! use rusty_rubik::parser::parse_scramble;
+//!
+//! fn main() {
+//!     // the cross is untouched, but the F2L is not
+//!     let seq = MoveSequence(parse_scramble("R U R'").unwrap());
+//!     let state = CubeState::default().apply_move_instances(&seq);
+//!
+//!     assert!(MaskedState::new(&state, &PieceMask::cross()).is_solved());
+//!     assert!(!MaskedState::new(&state, &PieceMask::f2l()).is_solved());
+//! }
+//! ```
+
+use crate::cube::*;
+
+/// The labels of the pieces of a state, and whether their orientations
+/// are seen, indexed by piece.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct PieceMask {
+    /// The label of each corner, or `None` if the corner is a wildcard.
+    pub corners: [Option<u8>; 8],
+    /// The label of each edge, or `None` if the edge is a wildcard.
+    pub edges: [Option<u8>; 12],
+    /// Whether the orientation of each corner is seen.
+    pub corner_orientations: [bool; 8],
+    /// Whether the orientation of each edge is seen.
+    pub edge_orientations: [bool; 12],
+}
+
+impl PieceMask {
+    /// Returns the mask that sees every piece, so that masked states are
+    /// as good as full states.
+    pub fn all() -> Self {
+        let mut mask = PieceMask::none();
+        for i in 0..8 {
+            mask.see_corner(i, i as u8);
+        }
+        for i in 0..12 {
+            mask.see_edge(i, i as u8);
+        }
+        mask
+    }
+
+    /// Returns the mask that sees nothing.
+    pub fn none() -> Self {
+        PieceMask {
+            corners: [None; 8],
+            edges: [None; 12],
+            corner_orientations: [false; 8],
+            edge_orientations: [false; 12],
+        }
+    }
+
+    /// Returns the mask of the four edges of the D face.
+    pub fn cross() -> Self {
+        let mut mask = PieceMask::none();
+        for i in 8..12 {
+            mask.see_edge(i, i as u8);
+        }
+        mask
+    }
+
+    /// Returns the mask of the cross and the front-right F2L pair.
+    pub fn xcross() -> Self {
+        let mut mask = PieceMask::cross();
+        mask.see_corner(5, 5);
+        mask.see_edge(6, 6);
+        mask
+    }
+
+    /// Returns the mask of the orientation of the edges, with the DF and
+    /// DB edges solved, as in ZZ.
+    pub fn eoline() -> Self {
+        let mut mask = PieceMask::eo();
+        mask.see_edge(8, 8);
+        mask.see_edge(10, 10);
+        mask
+    }
+
+    /// Returns the mask of the 1x2x3 block on the left, as in Roux.
+    pub fn first_block() -> Self {
+        let mut mask = PieceMask::none();
+        for &i in [4, 7].iter() {
+            mask.see_corner(i, i as u8);
+        }
+        for &i in [4, 7, 11].iter() {
+            mask.see_edge(i, i as u8);
+        }
+        mask
+    }
+
+    /// Returns the mask of the 2x2x3 block on the bottom and back, as in Petrus.
+    pub fn block_2x2x3() -> Self {
+        let mut mask = PieceMask::none();
+        for &i in [6, 7].iter() {
+            mask.see_corner(i, i as u8);
+        }
+        for &i in [4, 5, 9, 10, 11].iter() {
+            mask.see_edge(i, i as u8);
+        }
+        mask
+    }
+
+    /// Returns the mask of the pieces of the first two layers, below the U face.
+    pub fn f2l() -> Self {
+        let mut mask = PieceMask::cross();
+        for i in 4..8 {
+            mask.see_corner(i, i as u8);
+            mask.see_edge(i, i as u8);
+        }
+        mask
+    }
+
+    /// Returns the mask of the first two layers and the orientation of the
+    /// last layer, ignoring the permutation of the last layer.
+    pub fn oll() -> Self {
+        let mut mask = PieceMask::f2l();
+        for i in 0..4 {
+            mask.see_corner(i, 0);
+            mask.see_edge(i, 0);
+        }
+        mask
+    }
+
+    /// Returns the mask of the orientation of the edges.
+    pub fn eo() -> Self {
+        PieceMask {
+            edge_orientations: [true; 12],
+            ..PieceMask::none()
+        }
+    }
+
+    /// Returns the mask of domino reduction on the U/D axis: the
+    /// orientation of every piece, and whether each edge belongs in the
+    /// E slice.
+    pub fn domino() -> Self {
+        let mut mask = PieceMask::none();
+        for i in 0..8 {
+            mask.see_corner(i, 0);
+        }
+        for i in 0..12 {
+            mask.see_edge(i, if (4..8).contains(&i) { 1 } else { 0 });
+        }
+        mask
+    }
+
+    fn see_corner(&mut self, piece: usize, label: u8) {
+        self.corners[piece] = Some(label);
+        self.corner_orientations[piece] = true;
+    }
+
+    fn see_edge(&mut self, piece: usize, label: u8) {
+        self.edges[piece] = Some(label);
+        self.edge_orientations[piece] = true;
+    }
+}
+
+/// A state of the Rubik's Cube seen through a `PieceMask`, indexed by
+/// position like `CubeState`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct MaskedState {
+    /// The label of the corner at each position.
+    pub cp: [Option<u8>; 8],
+    /// The orientation of the corner at each position, if it is seen.
+    pub co: [Option<i8>; 8],
+    /// The label of the edge at each position.
+    pub ep: [Option<u8>; 12],
+    /// The orientation of the edge at each position, if it is seen.
+    pub eo: [Option<i8>; 12],
+    mask: PieceMask,
+}
+
+impl MaskedState {
+    /// Returns what can be seen of a state through a mask.
+    pub fn new(state: &CubeState, mask: &PieceMask) -> Self {
+        let mut masked = MaskedState {
+            cp: [None; 8],
+            co: [None; 8],
+            ep: [None; 12],
+            eo: [None; 12],
+            mask: *mask,
+        };
+        for i in 0..8 {
+            let piece = state.cp[i] as usize;
+            masked.cp[i] = mask.corners[piece];
+            masked.co[i] = Some(state.co[i]).filter(|_| mask.corner_orientations[piece]);
+        }
+        for i in 0..12 {
+            let piece = state.ep[i] as usize;
+            masked.ep[i] = mask.edges[piece];
+            masked.eo[i] = Some(state.eo[i]).filter(|_| mask.edge_orientations[piece]);
+        }
+        masked
+    }
+
+    /// Returns the solved state seen through a mask, which is the goal
+    /// of a partial solve.
+    pub fn solved(mask: &PieceMask) -> Self {
+        MaskedState::new(&CubeState::default(), mask)
+    }
+
+    /// Returns the mask this state is seen through.
+    pub fn get_mask(&self) -> &PieceMask {
+        &self.mask
+    }
+
+    /// Returns the masked state reached by performing `other` on this
+    /// state, as in `CubeState::compose`.
+    pub fn compose(&self, other: &CubeState) -> Self {
+        let mut result = self.clone();
+        for i in 0..8 {
+            let from = other.cp[i] as usize;
+            result.cp[i] = self.cp[from];
+            result.co[i] = self.co[from].map(|co| normalize_twist(co + other.co[i]));
+        }
+        for i in 0..12 {
+            let from = other.ep[i] as usize;
+            result.ep[i] = self.ep[from];
+            result.eo[i] = self.eo[from].map(|eo| (eo + other.eo[i]) % 2);
+        }
+        result
+    }
+
+    /// Applies a move to this masked state.
+    pub fn apply_move_instance(&self, m: &MoveInstance) -> Self {
+        self.compose(&CubeState::default().apply_move_instance(m))
+    }
+
+    /// Applies a sequence of moves, in order, to this masked state.
+    pub fn apply_move_instances(&self, moves: &MoveSequence) -> Self {
+        self.compose(&CubeState::default().apply_move_instances(moves))
+    }
+
+    /// Returns `true` if this masked state looks solved under its mask.
+    pub fn is_solved(&self) -> bool {
+        *self == MaskedState::solved(&self.mask)
+    }
+
+    /// Returns `true` if a full state looks like this masked state under
+    /// its mask.
+    pub fn matches(&self, state: &CubeState) -> bool {
+        MaskedState::new(state, &self.mask) == *self
+    }
+}
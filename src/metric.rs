@@ -0,0 +1,153 @@
+//! A module for counting moves in the common turn metrics.
+//!
+//! The metrics differ in which moves they count and how:
+//!
+//! - HTM (half turn metric): any turn of an outer layer counts as one move.
+//! - QTM (quarter turn metric): each quarter turn of an outer layer counts
+//!   as one move, so that half turns count as two.
+//! - STM (slice turn metric): any turn of any layer counts as one move.
+//! - ETM (execution turn metric): every move, including whole-cube
+//!   rotations, counts as one move.
+//! - ATM (axial turn metric): consecutive turns about the same axis
+//!   count as one move together, since they can be done at once.
+//!
+//! Whole-cube rotations are free in every metric except ETM. Wide moves
+//! turn a single outer layer relative to the rest of the cube, so they count
+//! as a single outer layer turn, while slice moves count as two.
+
+use crate::cube::*;
+
+/// The turn metrics for counting the moves of a sequence.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum Metric {
+    HTM,
+    QTM,
+    STM,
+    ETM,
+    ATM,
+}
+
+fn is_rotation(token: BaseMoveToken) -> bool {
+    matches!(
+        token,
+        BaseMoveToken::X | BaseMoveToken::Y | BaseMoveToken::Z
+    )
+}
+
+fn is_slice(token: BaseMoveToken) -> bool {
+    matches!(
+        token,
+        BaseMoveToken::M | BaseMoveToken::E | BaseMoveToken::S
+    )
+}
+
+/// Returns the outer layer turns and rotation, as numbers of quarter
+/// turns, that make up a single quarter turn of a slice or wide move.
+fn get_decomposition(token: BaseMoveToken) -> Option<[(BaseMoveToken, u8); 3]> {
+    use BaseMoveToken::*;
+    match token {
+        M => Some([(L, 3), (R, 1), (X, 3)]),
+        E => Some([(U, 1), (D, 3), (Y, 3)]),
+        S => Some([(F, 3), (B, 1), (Z, 1)]),
+        Rw => Some([(L, 1), (R, 0), (X, 1)]),
+        Lw => Some([(L, 0), (R, 1), (X, 3)]),
+        Uw => Some([(U, 0), (D, 1), (Y, 1)]),
+        Dw => Some([(U, 1), (D, 0), (Y, 3)]),
+        Fw => Some([(F, 0), (B, 1), (Z, 1)]),
+        Bw => Some([(F, 1), (B, 0), (Z, 3)]),
+        _ => None,
+    }
+}
+
+impl Metric {
+    /// Returns the number of moves a single move counts as in this metric.
+    ///
+    /// For ATM, this is the count of the move on its own.
+    pub fn get_move_count(&self, m: &MoveInstance) -> usize {
+        let rotation = is_rotation(m.basemove);
+        let layers = if is_slice(m.basemove) { 2 } else { 1 };
+        match self {
+            Metric::ETM => 1,
+            _ if rotation => 0,
+            Metric::HTM => layers,
+            Metric::QTM => layers * if m.dir == Direction::Double { 2 } else { 1 },
+            Metric::STM | Metric::ATM => 1,
+        }
+    }
+
+    /// Returns `true` if a move is one of the moves of this metric,
+    /// which sequences converted to this metric are made of.
+    ///
+    /// HTM sequences are made of outer layer turns and rotations, and QTM
+    /// sequences additionally have no half turns of outer layers. The other
+    /// metrics allow any move.
+    pub fn is_metric_move(&self, m: &MoveInstance) -> bool {
+        match self {
+            Metric::HTM => get_decomposition(m.basemove).is_none(),
+            Metric::QTM => {
+                get_decomposition(m.basemove).is_none()
+                    && (is_rotation(m.basemove) || m.dir != Direction::Double)
+            }
+            Metric::STM | Metric::ETM | Metric::ATM => true,
+        }
+    }
+}
+
+impl MoveSequence {
+    /// Returns the number of moves of this sequence in a metric.
+    pub fn get_move_count(&self, metric: Metric) -> usize {
+        if metric != Metric::ATM {
+            return self
+                .get_moves()
+                .iter()
+                .map(|m| metric.get_move_count(m))
+                .sum();
+        }
+        // each run of turns about the same axis counts as one move
+        let mut count = 0;
+        let mut last_axis = None;
+        for m in self.get_moves() {
+            if is_rotation(m.basemove) {
+                last_axis = None;
+                continue;
+            }
+            let axis = get_axis(m.basemove);
+            if last_axis != Some(axis) {
+                count += 1;
+                last_axis = Some(axis);
+            }
+        }
+        count
+    }
+
+    /// Rewrites this sequence using only the moves of a metric, giving a
+    /// sequence that reaches the same state.
+    ///
+    /// Slice and wide moves are replaced by outer layer turns and a
+    /// rotation, and for QTM, half turns of outer layers are replaced
+    /// by two quarter turns (e.g. `R2` becomes `R R`).
+    pub fn convert_to_metric(&self, metric: Metric) -> Self {
+        let mut moves = vec![];
+        for m in self.get_moves() {
+            let parts = match get_decomposition(m.basemove) {
+                Some(parts) if !metric.is_metric_move(m) => parts.to_vec(),
+                _ => vec![(m.basemove, 1)],
+            };
+            for (basemove, turns) in parts {
+                let turns = (turns * m.dir.get_num_turns()) % 4;
+                if let Some(dir) = Direction::from_num_turns(turns) {
+                    let part = MoveInstance::new(basemove, dir);
+                    if metric.is_metric_move(&part) {
+                        moves.push(part);
+                    } else {
+                        // only half turns are left to split
+                        let quarter = MoveInstance::new(basemove, Direction::Normal);
+                        moves.push(quarter);
+                        moves.push(quarter);
+                    }
+                }
+            }
+        }
+        MoveSequence(moves)
+    }
+}
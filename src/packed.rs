@@ -0,0 +1,127 @@
+//! A module for a compact representation of the Rubik's Cube.
+//!
+//! A `PackedState` stores a `CubeState` in the bits of a single `u128`,
+//! so that it can be copied, hashed and compared cheaply. This makes it
+//! suitable for the huge visited sets of breadth-first style searches.
+//!
+//! The fields are stored from the least significant bit upwards, with
+//! each position taking a fixed number of bits:
+//!
+//! - bits 0-23: corner permutation, 3 bits per corner
+//! - bits 24-39: corner orientation, 2 bits per corner
+//! - bits 40-87: edge permutation, 4 bits per edge
+//! - bits 88-99: edge orientation, 1 bit per edge
+//! - bits 100-117: centers, 3 bits per center
+//!
+//! Corner orientations are stored as 0, 1 or 2, with 2 standing for
+//! the value -1 of `CubeState`.
+
+use crate::cube::*;
+
+const CP_OFFSET: u32 = 0;
+const CO_OFFSET: u32 = 24;
+const EP_OFFSET: u32 = 40;
+const EO_OFFSET: u32 = 88;
+const CENTERS_OFFSET: u32 = 100;
+
+/// A configuration of the Rubik's Cube packed into 118 bits.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PackedState(pub u128);
+
+impl Default for PackedState {
+    /// The solved state.
+    fn default() -> Self {
+        PackedState::from(&CubeState::default())
+    }
+}
+
+impl PackedState {
+    fn get(&self, offset: u32, width: u32, i: usize) -> u8 {
+        ((self.0 >> (offset + width * i as u32)) & ((1 << width) - 1)) as u8
+    }
+
+    fn set(&mut self, offset: u32, width: u32, i: usize, val: u8) {
+        let shift = offset + width * i as u32;
+        self.0 = (self.0 & !(((1 << width) - 1) << shift)) | ((val as u128) << shift);
+    }
+
+    fn apply_basemove(&self, m: &BaseMoveToken) -> Self {
+        let mov = get_move_matrix(m);
+        let mut result = *self;
+        for i in 0..8 {
+            let to = mov.cp_change[i] as usize;
+            let ori = (self.get(CO_OFFSET, 2, i) as i8 + mov.co_change[i] + 3) % 3;
+            result.set(CP_OFFSET, 3, to, self.get(CP_OFFSET, 3, i));
+            result.set(CO_OFFSET, 2, to, ori as u8);
+        }
+        for i in 0..12 {
+            let to = mov.ep_change[i] as usize;
+            let ori = (self.get(EO_OFFSET, 1, i) as i8 + mov.eo_change[i]) % 2;
+            result.set(EP_OFFSET, 4, to, self.get(EP_OFFSET, 4, i));
+            result.set(EO_OFFSET, 1, to, ori as u8);
+        }
+        for i in 0..6 {
+            let to = mov.center_change[i] as usize;
+            result.set(CENTERS_OFFSET, 3, to, self.get(CENTERS_OFFSET, 3, i));
+        }
+        result
+    }
+
+    /// Applies a move directly to the packed representation.
+    pub fn apply_move_instance(&self, m: &MoveInstance) -> Self {
+        (0..m.dir.get_num_turns()).fold(*self, |acc, _| acc.apply_basemove(&m.basemove))
+    }
+
+    /// Applies a sequence of moves, in order, directly to the packed representation.
+    pub fn apply_move_instances(&self, moves: &MoveSequence) -> Self {
+        moves
+            .get_moves()
+            .iter()
+            .fold(*self, |acc, mov| acc.apply_move_instance(mov))
+    }
+}
+
+impl From<&CubeState> for PackedState {
+    fn from(state: &CubeState) -> Self {
+        let mut packed = PackedState(0);
+        for i in 0..8 {
+            packed.set(CP_OFFSET, 3, i, state.cp[i]);
+            packed.set(CO_OFFSET, 2, i, ((state.co[i] + 3) % 3) as u8);
+        }
+        for i in 0..12 {
+            packed.set(EP_OFFSET, 4, i, state.ep[i]);
+            packed.set(EO_OFFSET, 1, i, state.eo[i] as u8);
+        }
+        for i in 0..6 {
+            packed.set(CENTERS_OFFSET, 3, i, state.centers[i]);
+        }
+        packed
+    }
+}
+
+impl From<CubeState> for PackedState {
+    fn from(state: CubeState) -> Self {
+        PackedState::from(&state)
+    }
+}
+
+impl From<PackedState> for CubeState {
+    fn from(packed: PackedState) -> Self {
+        let mut state = CubeState::default();
+        for i in 0..8 {
+            state.cp[i] = packed.get(CP_OFFSET, 3, i);
+            state.co[i] = match packed.get(CO_OFFSET, 2, i) {
+                2 => -1,
+                x => x as i8,
+            };
+        }
+        for i in 0..12 {
+            state.ep[i] = packed.get(EP_OFFSET, 4, i);
+            state.eo[i] = packed.get(EO_OFFSET, 1, i) as i8;
+        }
+        for i in 0..6 {
+            state.centers[i] = packed.get(CENTERS_OFFSET, 3, i);
+        }
+        state
+    }
+}
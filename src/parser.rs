@@ -10,12 +10,15 @@ fn parse_base_move(token: &str) -> Result<BaseMoveToken, strum::ParseError> {
 
 /// Parses a scramble sequence from a string.
 ///
+/// Besides face moves, wide moves (`Rw` or `r`), slice moves (`M`, `E`, `S`)
+/// and rotations (`x`, `y`, `z`) are accepted.
+///
 /// Returns a Result object indicating whether the parse was successful.
 pub fn parse_scramble(scramble: &str) -> Result<Vec<MoveInstance>, strum::ParseError> {
     let mut parsed = vec![];
-    let re_normal = Regex::new(r"^([UDLRFB])$").unwrap();
-    let re_prime = Regex::new(r"^([UDLRFB])'").unwrap();
-    let re_double = Regex::new(r"^([UDLRFB])2").unwrap();
+    let re_normal = Regex::new(r"^([UDLRFB]w?|[udlrfbMESxyz])$").unwrap();
+    let re_prime = Regex::new(r"^([UDLRFB]w?|[udlrfbMESxyz])'$").unwrap();
+    let re_double = Regex::new(r"^([UDLRFB]w?|[udlrfbMESxyz])2$").unwrap();
     let mut parse_error = "";
     'tokens: for token in scramble.split_whitespace() {
         if re_normal.is_match(token) {
@@ -47,7 +50,7 @@ pub fn parse_scramble(scramble: &str) -> Result<Vec<MoveInstance>, strum::ParseE
             break 'tokens;
         }
     }
-    if parse_error.len() > 0 {
+    if !parse_error.is_empty() {
         Err(strum::ParseError::VariantNotFound)
     } else {
         Ok(parsed)
@@ -4,10 +4,13 @@
 //! moves are needed to solve a given position of the Rubik's Cube. The intention
 //! is for these tables to be pre-generated before any solving work begins.
 //!
-//! Each table is generated by executing an iterative deepening DFS (IDDFS) starting
-//! from the solved state. For each state, the depth is recorded in a vector
+//! Each table is generated by a search starting from the solved state: a
+//! breadth-first search over the table indices for the corner and edge
+//! orientation tables, and an iterative deepening DFS (IDDFS) for the larger
+//! edge permutation table. For each state, the depth is recorded in a vector
 //! of the appropriate size.
 
+use crate::coord::*;
 use crate::cube::*;
 use std::io::Write;
 
@@ -31,21 +34,39 @@ pub struct PruningTables {
 impl PruningTables {
     /// Reads the default pruning tables from the default
     /// file names.
+    ///
+    /// Panics if a file cannot be read; see `read_default_tables`.
     pub fn default_tables() -> Self {
-        let corners = std::fs::read("corners.pt").unwrap();
-        let edges_o = std::fs::read("edges_o.pt").unwrap();
-        let edges_p = std::fs::read("edges_p.pt").unwrap();
-        PruningTables {
+        Self::read_default_tables().unwrap()
+    }
+
+    /// Reads the default pruning tables from the default file names,
+    /// or returns the error of the first file that cannot be read.
+    pub fn read_default_tables() -> std::io::Result<Self> {
+        let corners = std::fs::read("corners.pt")?;
+        let edges_o = std::fs::read("edges_o.pt")?;
+        let edges_p = std::fs::read("edges_p.pt")?;
+        Ok(PruningTables {
             corners,
             eo: edges_o,
             ep: edges_p,
-        }
+        })
     }
 
     /// Computes a lower bound on the number of moves needed to
     /// solve the given state, based on the pruning table values.
     pub fn compute_h_value(&self, state: &CubeState) -> u8 {
-        let (corners, eo, ep) = get_index_of_state(&state);
+        let (corners, eo, ep) = get_index_of_state(state);
+        std::cmp::max(
+            self.corners[corners as usize],
+            std::cmp::max(self.eo[eo as usize], self.ep[ep as usize]),
+        )
+    }
+
+    /// Computes a lower bound on the number of moves needed to
+    /// solve a state given by its coordinates.
+    pub fn compute_h_value_of_coord(&self, coord: &CoordState) -> u8 {
+        let (corners, eo, ep) = get_index_of_coord(coord);
         std::cmp::max(
             self.corners[corners as usize],
             std::cmp::max(self.eo[eo as usize], self.ep[ep as usize]),
@@ -55,62 +76,57 @@ impl PruningTables {
 
 /// A wrapper function around the main logic of IDDFS.
 fn iddfs(
-    starting_state: &CubeState,
+    starting_state: &CoordState,
     depth: u8,
-    mut bv: &mut [u8],
-    prop_func: &dyn Fn(&CubeState) -> usize,
-    tag: String,
+    bv: &mut [u8],
+    prop_func: &dyn Fn(&CoordState) -> usize,
 ) {
     if depth < 1 {
         panic!("Depth must be positive");
     }
     for d in 1..depth {
-        println!("Building {} pruning table for depth {}...", tag, d);
-        iddfs_search(&starting_state, d, d, &mut bv, 0, &prop_func);
+        iddfs_search(starting_state, d, d, bv, 0, &prop_func);
     }
 }
 
+/// Fills a pruning table breadth-first, starting from the solved state.
+///
+/// The indices of each depth are kept in a queue, and their states are
+/// recovered with `inv_prop_func` to apply every move to them. The search
+/// stops once a depth adds no new entries.
 fn fill_pruning_table(
-    original_depth: u8,
-    mut bv: &mut [u8],
-    allowed_moves: u8,
-    prop_func: &dyn Fn(&CubeState) -> usize,
-    inv_prop_func: &dyn Fn(usize) -> CubeState,
+    bv: &mut [u8],
+    prop_func: &dyn Fn(&CoordState) -> usize,
+    inv_prop_func: &dyn Fn(usize) -> CoordState,
 ) {
-    for d in 0..original_depth {
-        if d == 0 {
-            // in this case all entries are zero, so manually take the solved state
-        }
-        for i in 0..bv.len() {
-            if (original_depth == 1 && i == 0) || bv[i] == original_depth - 1 {
-                let new_cube_state = inv_prop_func(i);
-                for m in ALL_MOVES
-                    .iter()
-                    .filter(|mo| (1 << get_basemove_pos(mo.basemove)) & allowed_moves == 0)
-                {
-                    let new_state = state.apply_move_instance(m);
-                    let index = prop_func(&new_state);
-                    if index > 0
-                        && bv[index] != 0
-                        && bv[index] != 0
-                        && bv[index] < original_depth - 1
-                    {
-                        continue;
-                    }
+    // the solved state is the only entry of depth zero
+    let mut frontier = vec![0_u32];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = vec![];
+        for &i in frontier.iter() {
+            let state = inv_prop_func(i as usize);
+            for m in 0..ALL_MOVES.len() {
+                let index = prop_func(&MOVE_TABLES.apply_move(&state, m));
+                if index > 0 && bv[index] == 0 {
+                    bv[index] = depth;
+                    next.push(index as u32);
                 }
             }
         }
+        frontier = next;
     }
 }
 
 /// Starts a depth-bounded DFS from the given state.
 fn iddfs_search(
-    state: &CubeState,
+    state: &CoordState,
     original_depth: u8,
     d: u8,
-    mut bv: &mut [u8],
+    bv: &mut [u8],
     allowed_moves: u8,
-    prop_func: &dyn Fn(&CubeState) -> usize,
+    prop_func: &dyn Fn(&CoordState) -> usize,
 ) {
     if d == 0 {
         // cool, we've hit the desired depth now
@@ -119,11 +135,12 @@ fn iddfs_search(
             bv[index] = original_depth;
         }
     } else {
-        for m in ALL_MOVES
+        for (i, m) in ALL_MOVES
             .iter()
-            .filter(|mo| (1 << get_basemove_pos(mo.basemove)) & allowed_moves == 0)
+            .enumerate()
+            .filter(|(_, mo)| (1 << get_basemove_pos(mo.basemove)) & allowed_moves == 0)
         {
-            let new_state = state.apply_move_instance(m);
+            let new_state = MOVE_TABLES.apply_move(state, i);
             let index = prop_func(&new_state);
             if index > 0 && bv[index] != 0 && bv[index] < original_depth - d + 1 {
                 continue;
@@ -133,7 +150,7 @@ fn iddfs_search(
                 &new_state,
                 original_depth,
                 d - 1,
-                &mut bv,
+                bv,
                 new_allowed_moves,
                 &prop_func,
             );
@@ -143,59 +160,46 @@ fn iddfs_search(
 
 fn write_table(table: &[u8], filename: String) {
     let mut file = std::fs::File::create(filename).expect("Unable to create file.");
-    file.write(table).expect("Unable to write to file.");
+    file.write_all(table).expect("Unable to write to file.");
 }
 
 /// Generates a pruning table for the corners of a Rubik's Cube.
 pub fn generate_pruning_table_corners(filename: String) -> bool {
-    let solved = CubeState::default();
-    let mut table = vec![0 as u8; 88179840];
-    iddfs(
-        &solved,
-        9,
+    let mut table = vec![0_u8; 88179840];
+    fill_pruning_table(
         &mut table,
-        &|state: &CubeState| {
-            let (corner, _, _) = get_index_of_state(state);
-            corner as usize
+        &|state| state.corner_perm as usize * N_TWIST + state.twist as usize,
+        &|index| CoordState {
+            corner_perm: (index / N_TWIST) as u16,
+            twist: (index % N_TWIST) as u16,
+            ..CoordState::solved()
         },
-        String::from("corners"),
     );
-    write_table(&*table, filename);
+    write_table(&table, filename);
     true
 }
 
 /// Generates a pruning table for the edge orientation of a Rubik's Cube.
 pub fn generate_pruning_table_eo(filename: String) -> bool {
-    let solved = CubeState::default();
-    let mut table = vec![0 as u8; 2048];
-    iddfs(
-        &solved,
-        8,
-        &mut table,
-        &|state| {
-            let (_, index, _) = get_index_of_state(&state);
-            index as usize
-        },
-        String::from("EO"),
-    );
-    write_table(&*table, filename);
+    let mut table = vec![0_u8; 2048];
+    fill_pruning_table(&mut table, &|state| state.flip as usize, &|index| {
+        CoordState {
+            flip: index as u16,
+            ..CoordState::solved()
+        }
+    });
+    write_table(&table, filename);
     true
 }
 
 /// Generates a pruning table for the edge permutation of a Rubik's Cube.
 pub fn generate_pruning_table_ep(filename: String) -> bool {
-    let solved = CubeState::default();
-    let mut table = vec![0 as u8; 479001600];
-    iddfs(
-        &solved,
-        9,
-        &mut table,
-        &|state| {
-            let (_, _, index) = get_index_of_state(&state);
-            index as usize
-        },
-        String::from("EP"),
-    );
-    write_table(&*table, filename);
+    let solved = CoordState::solved();
+    let mut table = vec![0_u8; 479001600];
+    iddfs(&solved, 9, &mut table, &|state| {
+        let (_, _, index) = get_index_of_coord(state);
+        index as usize
+    });
+    write_table(&table, filename);
     true
 }
@@ -1,15 +1,16 @@
-use num_derive::FromPrimitive;
-use num_traits::FromPrimitive;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-use colored::Colorize;
-use lazy_static::lazy_static;
+use crate::cube::*;
+use crate::parser::parse_scramble;
+use crate::pruning::PruningTables;
+use crate::render::render_net;
+use crate::solver::{IDASolver, Solver};
+use pyo3::exceptions::{PyOSError, PyValueError};
 use pyo3::prelude::*;
-use std::collections::{HashMap, HashSet};
-use std::ops::{Add, Rem};
+use std::collections::HashSet;
 
-/**
+/*
 pub struct CubeState {
 }
 is an example for a 3x3
@@ -20,7 +21,7 @@ have a macro to generate these states?
 pub trait PuzzleMove {}
 
 /// General trait to describe a twisty puzzle.
-pub trait Puzzle {
+pub trait Puzzle: Sized {
     type M;
 
     // Initializes this puzzle in its solved state.
@@ -35,8 +36,12 @@ pub trait Puzzle {
     // moves will restrict others from being allowed.
     fn get_allowed_moves(&self) -> HashSet<Self::M>;
 
-    fn make_move(&self, m: Self::M) -> Self;
-    fn make_move_mut(&mut self, m: Self::M) -> &Self;
+    // Applies a move, or returns None if the move is not allowed.
+    fn make_move(&self, m: Self::M) -> Option<Self>;
+
+    // Applies a move in place, or returns None and leaves the puzzle
+    // unchanged if the move is not allowed.
+    fn make_move_mut(&mut self, m: Self::M) -> Option<&Self>;
 }
 
 #[derive(Clone)]
@@ -92,7 +97,7 @@ pub struct Cube6 {
 //     }
 // }
 
-#[derive(Debug, EnumIter, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive)]
+#[derive(Debug, EnumIter, Eq, PartialEq, Hash, Copy, Clone)]
 pub enum CubeAxis {
     U,
     D,
@@ -133,42 +138,110 @@ impl Default for Cube3 {
     fn default() -> Self {
         Self {
             cp: [0, 1, 2, 3, 4, 5, 6, 7],
-            co: [0 as u8; 8],
+            co: [0_u8; 8],
             ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
-            eo: [0 as u8; 12],
+            eo: [0_u8; 12],
         }
     }
 }
 
-// move actions?
-
-pub fn apply_permutation<T: Clone + Copy, const N: usize>(
-    og_state: [T; N],
-    delta: &[usize; N],
-    count: u8,
-) -> [T; N] {
-    let mut new_array = og_state.clone();
-    for _ in 0..count {
-        for i in 0..N {
-            new_array[delta[i]] = og_state[i];
+impl CubeMove {
+    /// Returns the equivalent `MoveInstance`, or `None` if the move turns
+    /// more than two layers or does not turn at all.
+    ///
+    /// The rotation is the number of clockwise quarter turns, so that a
+    /// rotation of 3 is a counter-clockwise turn.
+    pub fn to_move_instance(&self) -> Option<MoveInstance> {
+        use BaseMoveToken::*;
+        let moves = match self.depth {
+            1 => [U, D, L, R, F, B],
+            2 => [Uw, Dw, Lw, Rw, Fw, Bw],
+            _ => return None,
+        };
+        Some(MoveInstance::new(
+            *moves.get(self.axis as usize)?,
+            Direction::from_num_turns(self.rotation)?,
+        ))
+    }
+
+    /// Returns the equivalent `CubeMove` of a face turn or wide move,
+    /// or `None` for slice moves and rotations.
+    pub fn from_move_instance(m: &MoveInstance) -> Option<Self> {
+        use BaseMoveToken::*;
+        let (axis, depth) = match m.basemove {
+            U => (CubeAxis::U, 1),
+            D => (CubeAxis::D, 1),
+            L => (CubeAxis::L, 1),
+            R => (CubeAxis::R, 1),
+            F => (CubeAxis::F, 1),
+            B => (CubeAxis::B, 1),
+            Uw => (CubeAxis::U, 2),
+            Dw => (CubeAxis::D, 2),
+            Lw => (CubeAxis::L, 2),
+            Rw => (CubeAxis::R, 2),
+            Fw => (CubeAxis::F, 2),
+            Bw => (CubeAxis::B, 2),
+            _ => return None,
+        };
+        Some(CubeMove {
+            axis: axis as u8,
+            depth,
+            rotation: m.dir.get_num_turns(),
+        })
+    }
+}
+
+/// Converts a state, ignoring its centers since `Cube3` does not track them.
+impl From<&CubeState> for Cube3 {
+    fn from(state: &CubeState) -> Self {
+        Cube3 {
+            cp: state.cp,
+            co: state.co.map(|x| ((x + 3) % 3) as u8),
+            ep: state.ep,
+            eo: state.eo.map(|x| x as u8),
         }
     }
-    new_array
 }
 
-pub fn apply_orientation<T: Clone + Copy + Add<Output = T> + Rem<Output = T>, const N: usize>(
-    og_state: [T; N],
-    delta: &[T; N],
-    num_orientations: T,
-    count: u8,
-) -> [T; N] {
-    let mut new_array = og_state.clone();
-    for _ in 0..count {
-        for i in 0..N {
-            new_array[i] = (og_state[i] + delta[i]) % num_orientations;
+impl From<CubeState> for Cube3 {
+    fn from(state: CubeState) -> Self {
+        Cube3::from(&state)
+    }
+}
+
+/// Converts a cube to the equivalent state, whose corner orientations are
+/// stored as -1, 0 and 1 instead of 0, 1 and 2, with the centers in their
+/// home positions.
+impl From<&Cube3> for CubeState {
+    fn from(cube: &Cube3) -> Self {
+        CubeState {
+            cp: cube.cp,
+            co: cube.co.map(|x| if x == 2 { -1 } else { x as i8 }),
+            ep: cube.ep,
+            eo: cube.eo.map(|x| x as i8),
+            ..CubeState::default()
         }
     }
-    new_array
+}
+
+impl From<Cube3> for CubeState {
+    fn from(cube: Cube3) -> Self {
+        CubeState::from(&cube)
+    }
+}
+
+impl Cube3 {
+    /// Applies a sequence of moves, using the move tables of `CubeState`.
+    ///
+    /// Returns `None` if the sequence leaves the centers out of their home
+    /// positions, which `Cube3` cannot represent.
+    pub fn apply_move_instances(&self, moves: &MoveSequence) -> Option<Self> {
+        let state = CubeState::from(self).apply_move_instances(moves);
+        if state.centers != CubeState::default().centers {
+            return None;
+        }
+        Some(Cube3::from(&state))
+    }
 }
 
 impl Puzzle for Cube3 {
@@ -181,7 +254,7 @@ impl Puzzle for Cube3 {
     fn get_all_moves() -> HashSet<Self::M> {
         let mut all_moves = HashSet::new();
         for axis in CubeAxis::iter() {
-            for rotation in 0..3 {
+            for rotation in 1..=3 {
                 all_moves.insert(CubeMove {
                     axis: axis as u8,
                     depth: 1,
@@ -196,18 +269,44 @@ impl Puzzle for Cube3 {
         <Self as Puzzle>::get_all_moves()
     }
 
-    fn make_move(&self, m: CubeMove) -> Self {
-        let axis = <CubeAxis as FromPrimitive>::from_u8(m.axis).unwrap();
-        Cube3 {
-            cp: apply_permutation(self.cp, CP_DELTAS.get(&axis).unwrap(), m.rotation),
-            co: apply_orientation(self.co, CO_DELTAS.get(&axis).unwrap(), 3, m.rotation),
-            ep: apply_permutation(self.ep, EP_DELTAS.get(&axis).unwrap(), m.rotation),
-            eo: apply_orientation(self.eo, EO_DELTAS.get(&axis).unwrap(), 2, m.rotation),
-        }
+    /// Applies a face turn.
+    ///
+    /// Returns `None` if the move is not one of `get_all_moves`, since
+    /// other moves would take the centers out of their home positions.
+    fn make_move(&self, m: CubeMove) -> Option<Self> {
+        m.to_move_instance()
+            .filter(|_| m.depth == 1)
+            .and_then(|mov| self.apply_move_instances(&MoveSequence(vec![mov])))
+    }
+
+    fn make_move_mut(&mut self, m: CubeMove) -> Option<&Self> {
+        *self = Puzzle::make_move(self, m)?;
+        Some(self)
+    }
+}
+
+impl Puzzle for CubeState {
+    type M = MoveInstance;
+
+    fn new() -> Self {
+        CubeState::default()
     }
 
-    fn make_move_mut(&mut self, m: CubeMove) -> &Self {
-        self
+    fn get_all_moves() -> HashSet<Self::M> {
+        ALL_MOVES.iter().copied().collect()
+    }
+
+    fn get_allowed_moves(&self) -> HashSet<Self::M> {
+        <Self as Puzzle>::get_all_moves()
+    }
+
+    fn make_move(&self, m: MoveInstance) -> Option<Self> {
+        Some(self.apply_move_instance(&m))
+    }
+
+    fn make_move_mut(&mut self, m: MoveInstance) -> Option<&Self> {
+        *self = self.apply_move_instance(&m);
+        Some(self)
     }
 }
 
@@ -220,81 +319,40 @@ impl Cube3 {
 
     #[staticmethod]
     fn get_all_moves() -> HashSet<<Self as Puzzle>::M> {
-        HashSet::<CubeMove>::new()
+        <Self as Puzzle>::get_all_moves()
     }
 
     fn pretty_print(&self) {
-        println!("{}", "Hello world!".blue());
+        println!("{}", render_net(&CubeState::from(self)));
     }
 
-    // fn make_move(&self, m: CubeMove) -> Self {
-    //     Puzzle::make_move(&self, m)
-    // }
-
-    // fn make_move(&self, m: &str) -> Self {
-    //     self
-    // }
-
-    fn make_move(&self, m: &PyAny) -> Self {
-        if let Ok(x) = m.extract::<String>() {}
-        self.clone()
+    /// Applies either a `CubeMove` or a sequence of moves in the usual
+    /// notation, e.g. `"R U R' U'"`.
+    fn make_move(&self, m: &PyAny) -> PyResult<Self> {
+        let moves = if let Ok(notation) = m.extract::<String>() {
+            parse_scramble(&notation).map_err(|e| PyValueError::new_err(e.to_string()))?
+        } else {
+            let m = m.extract::<CubeMove>()?;
+            let mov = m
+                .to_move_instance()
+                .ok_or_else(|| PyValueError::new_err(format!("invalid move {:?}", m)))?;
+            vec![mov]
+        };
+        self.apply_move_instances(&MoveSequence(moves))
+            .ok_or_else(|| PyValueError::new_err("Cube3 does not support moving the centers"))
     }
-}
 
-// I want:
-// let cube = RubiksCube::new();
-// cube.make_move_mut("R");
-//
-// in python:
-// cube = Cube3("U D")
-
-macro_rules! hashmap {
-    ($cnt:ty,$($key:expr => $value:expr),+ ,) => {
-        {
-            let mut m = HashMap::<CubeAxis, $cnt>::new();
-            $(
-                m.insert($key, $value);
-            )+
-            m
-        }
-    };
-}
+    fn is_solved(&self) -> bool {
+        CubeState::from(self) == CubeState::default()
+    }
 
-lazy_static! {
-    static ref CP_DELTAS: HashMap<CubeAxis, [usize; 8]> = hashmap! {
-        [usize; 8],
-        CubeAxis::U => [1, 2, 3, 0, 4, 5, 6, 7],
-        CubeAxis::D => [0, 1, 2, 3, 5, 6, 7, 4],
-        CubeAxis::R => [0, 6, 1, 3, 4, 2, 5, 7],
-        CubeAxis::L => [3, 1, 2, 4, 7, 5, 6, 0],
-        CubeAxis::F => [0, 1, 5, 2, 3, 4, 6, 7],
-        CubeAxis::B => [7, 0, 2, 3, 4, 5, 1, 6],
-    };
-    static ref CO_DELTAS: HashMap<CubeAxis, [u8; 8]> = hashmap! {
-        [u8; 8],
-        CubeAxis::U => [0, 0, 0, 0, 0, 0, 0, 0],
-        CubeAxis::D => [0, 0, 0, 0, 0, 0, 0, 0],
-        CubeAxis::R => [0, 2, 1, 0, 0, 2, 1, 0],
-        CubeAxis::L => [1, 0, 0, 2, 1, 0, 0, 2],
-        CubeAxis::F =>[0, 0, 2, 1, 2, 1, 0, 0],
-        CubeAxis::B => [2, 1, 0, 0, 0, 0, 2, 1],
-    };
-    static ref EP_DELTAS: HashMap<CubeAxis, [usize; 12]> = hashmap! {
-        [usize; 12],
-        CubeAxis::U =>[1, 2, 3, 0, 4, 5, 6, 7, 8, 9, 10, 11],
-        CubeAxis::D =>[0, 1, 2, 3, 4, 5, 6, 7, 9, 10, 11, 8],
-        CubeAxis::R =>[0, 5, 2, 3, 4, 9, 1, 7, 8, 6, 10, 11],
-        CubeAxis::L =>[0, 1, 2, 7, 3, 5, 6, 11, 8, 9, 10, 4],
-        CubeAxis::F =>[0, 1, 6, 3, 4, 5, 8, 2, 7, 9, 10, 11],
-        CubeAxis::B =>[4, 1, 2, 3, 10, 0, 6, 7, 8, 9, 5, 11],
-    };
-    static ref EO_DELTAS: HashMap<CubeAxis, [u8; 12]> = hashmap! {
-        [u8; 12],
-        CubeAxis::U =>[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-        CubeAxis::D =>[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-        CubeAxis::R =>[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-        CubeAxis::L =>[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-        CubeAxis::F =>[0, 0, 1, 0, 0, 0, 1, 1, 1, 0, 0, 0],
-        CubeAxis::B =>[1, 0, 0, 0, 1, 1, 0, 0, 0, 0, 1, 0],
-    };
+    /// Returns an optimal solution, using the pruning tables in the
+    /// current directory.
+    ///
+    /// Raises an `OSError` if the pruning tables cannot be read.
+    fn solve(&self) -> PyResult<String> {
+        let tables = PruningTables::read_default_tables()
+            .map_err(|e| PyOSError::new_err(format!("cannot read the pruning tables: {}", e)))?;
+        Ok(IDASolver::new(self, &tables).solve().to_string())
+    }
 }
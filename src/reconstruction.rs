@@ -0,0 +1,396 @@
+//! A module for analyzing reconstructions of solves.
+//!
+//! A reconstruction is a scramble followed by the moves of a solution,
+//! split into labeled steps. Each step is checked against the state its
+//! label expects, e.g. a cross after the `cross` step, and its moves are
+//! counted in several metrics. If the times at which the steps end are
+//! known, the turns per second (TPS) are given as well, counting moves in
+//! STM.
+//!
+//! Solutions can be parsed from text with one step per line, in the form
+//! `moves // label`, optionally followed by `@` and the time in seconds
+//! at which the step ends, counted from the start of the solve. The
+//! recognized labels are listed in `StepGoal`; other labels are counted
+//! but not checked. The times must not decrease from one step to the next.
+//!
+//! Enabling the `serde` feature implements `Serialize` for the reports,
+//! e.g. to write them as JSON.
+//!
+//! ```
+//! use rusty_rubik::reconstruction::*;
+//!
+//! fn main() {
+//!     let solution = "
+//!         F U R U' R' F' // cross @ 1.5
+//!         R U' R' // f2l @ 3
+//!     ";
+//!     let recon = Reconstruction::parse("R U R' F R U R' U' F'", solution).unwrap();
+//!     let report = recon.analyze();
+//!
+//!     assert!(report.is_valid());
+//!     assert_eq!(report.steps[0].counts.htm, 6);
+//!     assert_eq!(report.tps, Some(3.0));
+//! }
+//! ```
+
+use crate::cube::*;
+use crate::metric::Metric;
+use crate::parser::parse_scramble;
+use crate::steps::Axis;
+use std::str::FromStr;
+
+/// The states that labeled steps are expected to reach.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum StepGoal {
+    /// A cross on any side, labeled `cross`.
+    Cross,
+    /// A cross with at least the given number of F2L pairs, labeled
+    /// `xcross` for one pair, or `pair 1` to `pair 4` and `f2l 1` to `f2l 4`.
+    Pairs(usize),
+    /// The first two layers, labeled `f2l`.
+    F2L,
+    /// An oriented last layer, labeled `oll`.
+    OLL,
+    /// Edge orientation on any axis, labeled `eo`.
+    EO,
+    /// The first Roux block, labeled `fb` or `first block`.
+    FirstBlock,
+    /// Both Roux blocks, labeled `sb` or `second block`.
+    SecondBlock,
+    /// The corners of the last layer in Roux, labeled `cmll`.
+    CMLL,
+    /// Domino reduction on any axis, labeled `dr` or `domino`.
+    Domino,
+    /// The solved cube, labeled `pll`, `lse`, `ll` or `solved`.
+    Solved,
+}
+
+impl FromStr for StepGoal {
+    type Err = ReconstructionError;
+
+    /// Parses a step label, ignoring case, spaces, dashes and underscores.
+    fn from_str(label: &str) -> Result<Self, Self::Err> {
+        let normalized: String = label
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .collect::<String>()
+            .to_lowercase();
+        let goal = match normalized.as_str() {
+            "cross" => StepGoal::Cross,
+            "xcross" => StepGoal::Pairs(1),
+            "f2l" => StepGoal::F2L,
+            "oll" => StepGoal::OLL,
+            "eo" => StepGoal::EO,
+            "fb" | "firstblock" => StepGoal::FirstBlock,
+            "sb" | "secondblock" => StepGoal::SecondBlock,
+            "cmll" => StepGoal::CMLL,
+            "dr" | "domino" => StepGoal::Domino,
+            "pll" | "lse" | "ll" | "solved" => StepGoal::Solved,
+            other => {
+                let pair = other
+                    .strip_prefix("pair")
+                    .or_else(|| other.strip_prefix("f2l"))
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .filter(|n| (1..=4).contains(n));
+                match pair {
+                    Some(n) => StepGoal::Pairs(n),
+                    None => return Err(ReconstructionError::UnknownLabel(label.to_string())),
+                }
+            }
+        };
+        Ok(goal)
+    }
+}
+
+impl StepGoal {
+    /// Returns `true` if a state has reached this goal.
+    pub fn is_reached(&self, state: &CubeState) -> bool {
+        match self {
+            StepGoal::Cross => state.is_cross_solved(),
+            StepGoal::Pairs(n) => state.count_solved_f2l_pairs() >= *n,
+            StepGoal::F2L => state.is_f2l_solved(),
+            StepGoal::OLL => state.is_oll_solved(),
+            StepGoal::EO => [Axis::UD, Axis::LR, Axis::FB]
+                .iter()
+                .any(|&axis| state.is_eo_solved(axis)),
+            StepGoal::FirstBlock => state.is_first_block_solved(),
+            StepGoal::SecondBlock => state.is_second_block_solved(),
+            StepGoal::CMLL => state.is_cmll_solved(),
+            StepGoal::Domino => state.is_domino_reduced(),
+            StepGoal::Solved => state.is_solved(),
+        }
+    }
+}
+
+/// An error encountered while parsing a reconstruction.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ReconstructionError {
+    InvalidScramble(String),
+    /// The moves on the given line, counting from 1, could not be parsed.
+    InvalidMoves(usize, String),
+    /// The given line has no label after its moves.
+    MissingLabel(usize),
+    /// The given line has a time that is not a non-negative number.
+    InvalidTime(usize, String),
+    /// The given line has a time earlier than the time of a previous step.
+    DecreasingTime(usize, String),
+    /// A label is not one of the labels of `StepGoal`.
+    UnknownLabel(String),
+}
+
+impl std::fmt::Display for ReconstructionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReconstructionError::InvalidScramble(s) => write!(f, "invalid scramble '{}'", s),
+            ReconstructionError::InvalidMoves(line, moves) => {
+                write!(f, "line {}: invalid moves '{}'", line, moves)
+            }
+            ReconstructionError::MissingLabel(line) => write!(f, "line {}: missing label", line),
+            ReconstructionError::InvalidTime(line, time) => {
+                write!(f, "line {}: invalid time '{}'", line, time)
+            }
+            ReconstructionError::DecreasingTime(line, time) => {
+                write!(
+                    f,
+                    "line {}: time '{}' is before the previous step",
+                    line, time
+                )
+            }
+            ReconstructionError::UnknownLabel(label) => write!(f, "unknown label '{}'", label),
+        }
+    }
+}
+
+impl std::error::Error for ReconstructionError {}
+
+/// A labeled step of a solution.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ReconstructionStep {
+    pub label: String,
+    pub moves: MoveSequence,
+    /// The time in seconds at which the step ends, from the start of the solve.
+    pub time: Option<f64>,
+}
+
+/// A scramble and the steps of its solution.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Reconstruction {
+    pub scramble: MoveSequence,
+    pub steps: Vec<ReconstructionStep>,
+}
+
+/// The number of moves of a sequence in each metric.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MoveCounts {
+    pub htm: usize,
+    pub qtm: usize,
+    pub stm: usize,
+    pub etm: usize,
+}
+
+impl MoveCounts {
+    /// Counts the moves of a sequence.
+    pub fn of(moves: &MoveSequence) -> Self {
+        MoveCounts {
+            htm: moves.get_move_count(Metric::HTM),
+            qtm: moves.get_move_count(Metric::QTM),
+            stm: moves.get_move_count(Metric::STM),
+            etm: moves.get_move_count(Metric::ETM),
+        }
+    }
+
+    fn add(&self, other: &MoveCounts) -> Self {
+        MoveCounts {
+            htm: self.htm + other.htm,
+            qtm: self.qtm + other.qtm,
+            stm: self.stm + other.stm,
+            etm: self.etm + other.etm,
+        }
+    }
+}
+
+/// The analysis of a step of a solution.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StepReport {
+    pub label: String,
+    /// The goal of the step, or `None` if its label is not recognized.
+    pub goal: Option<StepGoal>,
+    pub moves: MoveSequence,
+    pub counts: MoveCounts,
+    /// Whether the goal is reached after the step, if it has one.
+    pub reached: Option<bool>,
+    /// The time in seconds taken by the step, if its end and the end of
+    /// the previous step are known.
+    pub duration: Option<f64>,
+    pub tps: Option<f64>,
+}
+
+/// The analysis of a whole reconstruction.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReconstructionReport {
+    pub scramble: MoveSequence,
+    pub steps: Vec<StepReport>,
+    pub counts: MoveCounts,
+    /// Whether the cube is solved at the end of the solution.
+    pub solved: bool,
+    /// The time in seconds of the whole solve, if the last step's end is known.
+    pub time: Option<f64>,
+    pub tps: Option<f64>,
+}
+
+fn get_tps(counts: &MoveCounts, time: Option<f64>) -> Option<f64> {
+    time.filter(|&t| t > 0.0).map(|t| counts.stm as f64 / t)
+}
+
+fn parse_moves(moves: &str) -> Option<MoveSequence> {
+    parse_scramble(moves).ok().map(MoveSequence)
+}
+
+impl Reconstruction {
+    /// Parses a scramble and a solution with one step per line.
+    ///
+    /// Blank lines are skipped. The labels are not required to be
+    /// recognized, since other steps are still counted.
+    pub fn parse(scramble: &str, solution: &str) -> Result<Self, ReconstructionError> {
+        let scramble = parse_moves(scramble)
+            .ok_or_else(|| ReconstructionError::InvalidScramble(scramble.to_string()))?;
+        let mut steps: Vec<ReconstructionStep> = vec![];
+        for (i, line) in solution.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (moves, comment) = line
+                .split_once("//")
+                .ok_or(ReconstructionError::MissingLabel(i + 1))?;
+            let (label, time) = match comment.split_once('@') {
+                Some((label, time)) => {
+                    let time = time.trim();
+                    let seconds = time
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|t| t.is_finite() && *t >= 0.0)
+                        .ok_or_else(|| ReconstructionError::InvalidTime(i + 1, time.to_string()))?;
+                    let last_time = steps.iter().rev().find_map(|step| step.time);
+                    if last_time.is_some_and(|last| seconds < last) {
+                        return Err(ReconstructionError::DecreasingTime(i + 1, time.to_string()));
+                    }
+                    (label.trim(), Some(seconds))
+                }
+                None => (comment.trim(), None),
+            };
+            if label.is_empty() {
+                return Err(ReconstructionError::MissingLabel(i + 1));
+            }
+            let moves = parse_moves(moves).ok_or_else(|| {
+                ReconstructionError::InvalidMoves(i + 1, moves.trim().to_string())
+            })?;
+            steps.push(ReconstructionStep {
+                label: label.to_string(),
+                moves,
+                time,
+            });
+        }
+        Ok(Reconstruction { scramble, steps })
+    }
+
+    /// Returns the whole solution, with the steps in order.
+    pub fn get_solution(&self) -> MoveSequence {
+        MoveSequence(
+            self.steps
+                .iter()
+                .flat_map(|step| step.moves.get_moves().iter().copied())
+                .collect(),
+        )
+    }
+
+    /// Applies each step in turn to the scrambled cube, checking its goal
+    /// and counting its moves.
+    pub fn analyze(&self) -> ReconstructionReport {
+        let mut state = CubeState::default().apply_move_instances(&self.scramble);
+        let mut steps = vec![];
+        let mut counts = MoveCounts::default();
+        let mut last_time = Some(0.0);
+        for step in self.steps.iter() {
+            state = state.apply_move_instances(&step.moves);
+            let goal = StepGoal::from_str(&step.label).ok();
+            let step_counts = MoveCounts::of(&step.moves);
+            let duration = step.time.zip(last_time).map(|(end, start)| end - start);
+            counts = counts.add(&step_counts);
+            last_time = step.time;
+            steps.push(StepReport {
+                label: step.label.clone(),
+                goal,
+                moves: step.moves.clone(),
+                counts: step_counts,
+                reached: goal.map(|goal| goal.is_reached(&state)),
+                duration,
+                tps: get_tps(&step_counts, duration),
+            });
+        }
+        let time = self.steps.last().and_then(|step| step.time);
+        ReconstructionReport {
+            scramble: self.scramble.clone(),
+            steps,
+            counts,
+            solved: state.is_solved(),
+            time,
+            tps: get_tps(&counts, time),
+        }
+    }
+}
+
+fn format_timing(duration: Option<f64>, tps: Option<f64>) -> String {
+    match (duration, tps) {
+        (Some(duration), Some(tps)) => format!(", {:.2}s, {:.2} TPS", duration, tps),
+        (Some(duration), None) => format!(", {:.2}s", duration),
+        _ => String::new(),
+    }
+}
+
+impl ReconstructionReport {
+    /// Returns `true` if every recognized step reaches its goal and the
+    /// cube is solved at the end.
+    pub fn is_valid(&self) -> bool {
+        self.solved && self.steps.iter().all(|step| step.reached != Some(false))
+    }
+}
+
+/// Formats the report as text, with one line per step and a total.
+impl std::fmt::Display for ReconstructionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "scramble: {}", self.scramble)?;
+        for step in self.steps.iter() {
+            let counts = &step.counts;
+            write!(
+                f,
+                "{}: {} ({} HTM, {} QTM, {} STM, {} ETM{})",
+                step.label,
+                step.moves,
+                counts.htm,
+                counts.qtm,
+                counts.stm,
+                counts.etm,
+                format_timing(step.duration, step.tps)
+            )?;
+            if step.reached == Some(false) {
+                write!(f, " [not reached]")?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "total: {} HTM, {} QTM, {} STM, {} ETM{}, {}",
+            self.counts.htm,
+            self.counts.qtm,
+            self.counts.stm,
+            self.counts.etm,
+            format_timing(self.time, self.tps),
+            if self.solved { "solved" } else { "not solved" }
+        )
+    }
+}
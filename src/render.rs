@@ -0,0 +1,120 @@
+//! A module for rendering the Rubik's Cube in the terminal.
+//!
+//! The cube is drawn as an unfolded net, with the U face on top,
+//! the L, F, R and B faces in a row, and the D face on the bottom:
+//!
+//! ```text
+//!       U U U
+//!       U U U
+//!       U U U
+//! L L L F F F R R R B B B
+//! L L L F F F R R R B B B
+//! L L L F F F R R R B B B
+//!       D D D
+//!       D D D
+//!       D D D
+//! ```
+//!
+//! When the output is a terminal, each sticker is drawn as a colored
+//! block instead, using the standard Western color scheme.
+
+use crate::cube::CubeState;
+use crate::facelet::{ColorScheme, Face};
+use colored::{Color, Colorize};
+use std::io::IsTerminal;
+
+/// Returns the terminal color of the stickers of a face.
+fn get_terminal_color(face: Face) -> Color {
+    match face {
+        Face::U => Color::BrightWhite,
+        Face::R => Color::Red,
+        Face::F => Color::Green,
+        Face::D => Color::BrightYellow,
+        Face::L => Color::TrueColor {
+            r: 255,
+            g: 128,
+            b: 0,
+        },
+        Face::B => Color::Blue,
+    }
+}
+
+/// Lays out the faces of the net, drawing each sticker with `draw`.
+///
+/// `blank` is the padding used in place of a missing face, and
+/// `separator` is placed between adjacent stickers.
+fn render_with(
+    facelets: &[Face; 54],
+    draw: &dyn Fn(Face) -> String,
+    blank: &str,
+    separator: &str,
+) -> String {
+    // faces of each band of the net, as offsets into the facelet array
+    let bands: [&[Option<usize>]; 3] = [
+        &[None, Some(0)],
+        &[Some(36), Some(18), Some(9), Some(45)],
+        &[None, Some(27)],
+    ];
+    let mut lines = vec![];
+    for band in bands.iter() {
+        for row in 0..3 {
+            let mut cells = vec![];
+            for face in band.iter() {
+                match face {
+                    Some(offset) => {
+                        for col in 0..3 {
+                            cells.push(draw(facelets[offset + 3 * row + col]));
+                        }
+                    }
+                    None => cells.extend(vec![blank.to_string(); 3]),
+                }
+            }
+            lines.push(cells.join(separator).trim_end().to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+/// Renders the net of a state as plain text, denoting each sticker
+/// by its character in the given color scheme.
+pub fn render_net_ascii(state: &CubeState, scheme: &ColorScheme) -> String {
+    render_with(
+        &state.to_facelets(),
+        &|face| scheme.get_color(face).to_string(),
+        " ",
+        " ",
+    )
+}
+
+/// Renders the net of a state with a colored block for each sticker.
+pub fn render_net_colored(state: &CubeState) -> String {
+    render_with(
+        &state.to_facelets(),
+        &|face| "  ".on_color(get_terminal_color(face)).to_string(),
+        "  ",
+        "",
+    )
+}
+
+/// Renders the net of a state, using colored blocks if standard output
+/// is a terminal and plain text otherwise.
+pub fn render_net(state: &CubeState) -> String {
+    if std::io::stdout().is_terminal() {
+        render_net_colored(state)
+    } else {
+        render_net_ascii(state, &ColorScheme::default())
+    }
+}
+
+impl std::fmt::Display for CubeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", render_net_ascii(self, &ColorScheme::default()))
+    }
+}
+
+impl CubeState {
+    /// Prints the net of this state to standard output.
+    pub fn pretty_print(&self) {
+        println!("{}", render_net(self));
+    }
+}
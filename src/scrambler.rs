@@ -0,0 +1,32 @@
+//! A module for generating random-state scrambles.
+//!
+//! A random-state scramble is obtained by picking a configuration of the
+//! Rubik's Cube uniformly at random, solving it, and then inverting the
+//! solution. Applying the resulting sequence to a solved cube yields the
+//! chosen configuration, which is how the WCA generates official scrambles.
+//!
+//! The states are solved with the two-phase solver of `twophase`, which
+//! needs no pruning table files and takes a fraction of a second.
+
+use crate::cube::*;
+use crate::solver::Solver;
+use crate::twophase::TwoPhaseSolver;
+
+/// Generates a scramble sequence that takes the solved state to the given state.
+///
+/// The scramble is not the shortest one, but is usually between 20 and
+/// 30 moves long.
+pub fn scramble_for_state(state: impl Into<CubeState>) -> MoveSequence {
+    TwoPhaseSolver::new(state).solve().invert()
+}
+
+/// Generates a WCA-style random-state scramble.
+pub fn random_state_scramble() -> MoveSequence {
+    scramble_for_state(CubeState::random())
+}
+
+/// Generates a WCA-style random-state scramble from the given seed.
+/// The same seed always yields the same scramble.
+pub fn random_state_scramble_with_seed(seed: u64) -> MoveSequence {
+    scramble_for_state(CubeState::random_with_seed(seed))
+}
@@ -0,0 +1,203 @@
+//! Serde support for the cube types, enabled by the `serde` feature.
+//!
+//! Moves and move sequences are serialized as strings in the usual
+//! notation, e.g. `"R2"` and `"R U R' U'"`.
+//!
+//! States are serialized as a struct holding both their piece arrays
+//! (`cp`, `co`, `ep`, `eo` and `centers`) and their facelet string in the
+//! default color scheme (`facelets`). When deserializing, either form may be
+//! given on its own; if both are given, they must describe the same state.
+//! Either way, the state must be solvable, as checked by `CubeState::validate`.
+//! Corner orientations are always given as -1, 0 or 1, as in `CubeState`.
+
+use crate::cube::{CubeState, MoveInstance, MoveSequence};
+use crate::facelet::ColorScheme;
+use crate::parser::parse_scramble;
+use crate::puzzle::{Cube3, CubeAxis, CubeMove};
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use strum::IntoEnumIterator;
+
+impl Serialize for MoveInstance {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for MoveInstance {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let notation = String::deserialize(deserializer)?;
+        match parse_scramble(&notation).map_err(D::Error::custom)?[..] {
+            [m] => Ok(m),
+            _ => Err(D::Error::custom(format!(
+                "expected a single move, found '{}'",
+                notation
+            ))),
+        }
+    }
+}
+
+impl Serialize for MoveSequence {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for MoveSequence {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let notation = String::deserialize(deserializer)?;
+        Ok(MoveSequence(
+            parse_scramble(&notation).map_err(D::Error::custom)?,
+        ))
+    }
+}
+
+/// The serialized form of a state.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "CubeState")]
+struct StateRepr {
+    #[serde(default)]
+    cp: Option<[u8; 8]>,
+    #[serde(default)]
+    co: Option<[i8; 8]>,
+    #[serde(default)]
+    ep: Option<[u8; 12]>,
+    #[serde(default)]
+    eo: Option<[i8; 12]>,
+    #[serde(default)]
+    centers: Option<[u8; 6]>,
+    #[serde(default)]
+    facelets: Option<String>,
+}
+
+impl StateRepr {
+    fn into_state<E: serde::de::Error>(self) -> Result<CubeState, E> {
+        let from_facelets = match &self.facelets {
+            Some(facelets) => Some(
+                CubeState::from_facelet_string(facelets, &ColorScheme::default())
+                    .map_err(E::custom)?,
+            ),
+            None => None,
+        };
+        let from_pieces = match (self.cp, self.co, self.ep, self.eo) {
+            (Some(cp), Some(co), Some(ep), Some(eo)) => {
+                let state = CubeState {
+                    cp,
+                    co,
+                    ep,
+                    eo,
+                    centers: self.centers.unwrap_or(CubeState::default().centers),
+                };
+                state.validate().map_err(E::custom)?;
+                Some(state)
+            }
+            (None, None, None, None) => None,
+            _ => return Err(E::custom("expected all of cp, co, ep and eo")),
+        };
+        match (from_pieces, from_facelets) {
+            (Some(pieces), Some(facelets)) if pieces != facelets => {
+                Err(E::custom("the piece arrays and facelets do not match"))
+            }
+            (Some(state), _) | (None, Some(state)) => Ok(state),
+            (None, None) => Err(E::custom("expected either piece arrays or facelets")),
+        }
+    }
+}
+
+impl Serialize for CubeState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StateRepr {
+            cp: Some(self.cp),
+            co: Some(self.co),
+            ep: Some(self.ep),
+            eo: Some(self.eo),
+            centers: Some(self.centers),
+            facelets: Some(self.to_facelet_string(&ColorScheme::default())),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CubeState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        StateRepr::deserialize(deserializer)?.into_state()
+    }
+}
+
+impl Serialize for Cube3 {
+    /// Serializes the same way as the equivalent `CubeState`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CubeState::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cube3 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = CubeState::deserialize(deserializer)?;
+        if state.centers != CubeState::default().centers {
+            return Err(D::Error::custom("Cube3 does not support moved centers"));
+        }
+        Ok(Cube3::from(&state))
+    }
+}
+
+impl Serialize for CubeMove {
+    /// Serializes as the notation of the move, with the number of layers
+    /// turned given before wide moves of more than two layers (e.g. `3Rw2`).
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let axis = CubeAxis::iter()
+            .nth(self.axis as usize)
+            .ok_or_else(|| S::Error::custom(format!("invalid axis {}", self.axis)))?;
+        let layers = match self.depth {
+            0 => return Err(S::Error::custom("invalid depth 0")),
+            1 => format!("{:?}", axis),
+            2 => format!("{:?}w", axis),
+            n => format!("{}{:?}w", n, axis),
+        };
+        let suffix = match self.rotation {
+            1 => "",
+            2 => "2",
+            3 => "'",
+            r => return Err(S::Error::custom(format!("invalid rotation {}", r))),
+        };
+        serializer.serialize_str(&format!("{}{}", layers, suffix))
+    }
+}
+
+impl<'de> Deserialize<'de> for CubeMove {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let notation = String::deserialize(deserializer)?;
+        let invalid = || D::Error::custom(format!("invalid move '{}'", notation));
+        let digits = notation.chars().take_while(|c| c.is_ascii_digit()).count();
+        let rest = &notation[digits..];
+        let axis = CubeAxis::iter()
+            .position(|a| rest.starts_with(&format!("{:?}", a)))
+            .ok_or_else(invalid)?;
+        let rest = &rest[1..];
+        let (depth, rest) = match (digits, rest.strip_prefix('w')) {
+            (0, None) => (1, rest),
+            (0, Some(rest)) => (2, rest),
+            // an explicit depth is only written for more than two layers
+            (_, Some(rest)) if !notation.starts_with('0') => {
+                let depth: u8 = notation[..digits].parse().map_err(|_| invalid())?;
+                if depth < 3 {
+                    return Err(invalid());
+                }
+                (depth, rest)
+            }
+            (_, _) => return Err(invalid()),
+        };
+        let rotation = match rest {
+            "" => 1,
+            "2" => 2,
+            "'" => 3,
+            _ => return Err(invalid()),
+        };
+        Ok(CubeMove {
+            axis: axis as u8,
+            depth,
+            rotation,
+        })
+    }
+}
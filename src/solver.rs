@@ -1,9 +1,17 @@
 //! Contains utility methods of various puzzle solving methods.
 //!
-//! Includes A* search and iterative deepening A* (IDA*).
+//! Includes A* search and iterative deepening A* (IDA*). Besides the solved
+//! state, IDA* can solve to a partial goal such as the cross or the first
+//! block of Roux, given as a `Goal` to a `GoalSolver`. Since a goal may not
+//! be reachable from every state, a `GoalSolver` returns `None` instead of
+//! a solution then, and both searches can be bounded to a maximum depth.
 
+use crate::coord::*;
 use crate::cube::*;
+use crate::masked::MaskedState;
+use crate::packed::PackedState;
 use crate::pruning::PruningTables;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
@@ -21,6 +29,29 @@ pub trait Solver {
     fn solve(&self) -> MoveSequence;
 }
 
+/// A goal for `GoalSolver` to solve to instead of the solved state.
+pub trait Goal {
+    /// Returns `true` if a state reaches this goal.
+    fn is_reached(&self, state: &CubeState) -> bool;
+
+    /// Returns a lower bound on the number of moves needed to reach this
+    /// goal from a state, or `u8::MAX` if it is known that the goal cannot
+    /// be reached from it. The solutions found are only optimal if the
+    /// bound never overestimates.
+    fn get_h_value(&self, state: &CubeState) -> u8;
+}
+
+/// Returns the rotation bringing the centers of a state back home, as in
+/// `CubeState::get_home_rotation`, and the state reached after it.
+///
+/// The searches only turn the faces, so they solve the rotated state, and
+/// their solutions start with the rotation to reach the solved state.
+pub(crate) fn center_state(state: &CubeState) -> (Vec<MoveInstance>, CubeState) {
+    let rotation = state.get_home_rotation().unwrap_or_default();
+    let centered = state.apply_move_instances(&rotation);
+    (rotation.0, centered)
+}
+
 /**
  * A solver implementing the A* search algorithm.
  *
@@ -33,8 +64,10 @@ pub struct AStarSolver {
 }
 
 impl AStarSolver {
-    pub fn new(state: CubeState) -> Self {
-        AStarSolver { start_state: state }
+    pub fn new(state: impl Into<CubeState>) -> Self {
+        AStarSolver {
+            start_state: state.into(),
+        }
     }
 }
 
@@ -45,33 +78,35 @@ impl Solver for AStarSolver {
 
     fn solve(&self) -> MoveSequence {
         let mut queue = PriorityQueue::new();
-        let mut visited = HashSet::<CubeState>::new();
-        let mut come_from = HashMap::<CubeState, (CubeState, MoveInstance)>::new();
-        let mut g_scores = HashMap::<CubeState, i32>::new();
-
-        // TODO: need to compress cube state
-        queue.push(self.get_start_state().clone(), 0);
-        g_scores.insert(self.get_start_state().clone(), 0);
-        while queue.len() > 0 {
+        let mut visited = HashSet::<PackedState>::new();
+        let mut come_from = HashMap::<PackedState, (PackedState, MoveInstance)>::new();
+        let mut g_scores = HashMap::<PackedState, i32>::new();
+
+        let (rotation, start_state) = center_state(self.get_start_state());
+        let start = PackedState::from(&start_state);
+        let solved = PackedState::default();
+        queue.push(start, 0);
+        g_scores.insert(start, 0);
+        while !queue.is_empty() {
             if let Some((current, priority)) = queue.pop() {
-                if current == CubeState::default() {
+                if current == solved {
                     // we found the solved state!
                     break;
                 }
                 if visited.contains(&current) {
                     continue;
                 }
-                visited.insert(current.clone());
+                visited.insert(current);
                 // iterate through all moves
                 for m in ALL_MOVES.iter() {
                     let new_state = current.apply_move_instance(m);
                     let new_g_score = priority - 1;
-                    let neighbor_g_score = g_scores.get(&new_state).unwrap_or(&std::i32::MIN);
+                    let neighbor_g_score = g_scores.get(&new_state).unwrap_or(&i32::MIN);
                     if new_g_score > *neighbor_g_score {
-                        come_from.insert(new_state.clone(), (current.clone(), *m));
-                        g_scores.insert(new_state.clone(), new_g_score);
+                        come_from.insert(new_state, (current, *m));
+                        g_scores.insert(new_state, new_g_score);
                     }
-                    if let None = queue.get(&new_state) {
+                    if queue.get(&new_state).is_none() {
                         queue.push(new_state, priority - 1);
                     } else if let Some((_, p)) = queue.get(&new_state) {
                         if *p < priority - 1 {
@@ -82,16 +117,16 @@ impl Solver for AStarSolver {
             }
         }
         // now reconstruct the path
-        let mut curr = CubeState::default();
+        let mut curr = solved;
         let mut path = vec![];
-        while curr != self.get_start_state().clone() {
+        while curr != start {
             if let Some((c, m)) = come_from.get(&curr) {
-                path.push(m.clone());
-                curr = c.clone();
+                path.push(*m);
+                curr = *c;
             }
         }
         path.reverse();
-        MoveSequence(path)
+        MoveSequence([rotation, path].concat())
     }
 }
 
@@ -104,7 +139,16 @@ impl Solver for AStarSolver {
  */
 pub struct IDASolver<'a> {
     start_state: CubeState,
-    pruning_tables: &'a PruningTables,
+    tables: &'a PruningTables,
+}
+
+/**
+ * A solver finding an optimal sequence of moves reaching a `Goal` by IDA*,
+ * pruned by the heuristic of the goal.
+ */
+pub struct GoalSolver<'a> {
+    start_state: CubeState,
+    goal: &'a dyn Goal,
 }
 
 enum SearchResult {
@@ -112,36 +156,81 @@ enum SearchResult {
     NewBound(u8),
 }
 
+/// The states searched by IDA*, with the goal and heuristic of the search.
+trait SearchSpace {
+    type Node;
+
+    /// Applies the move at the given index of `ALL_MOVES`.
+    fn apply_move(&self, node: &Self::Node, index: usize) -> Self::Node;
+    fn get_h_value(&self, node: &Self::Node) -> u8;
+    fn is_goal(&self, node: &Self::Node) -> bool;
+}
+
+/// The search for the solved state runs on coordinates, using the
+/// precomputed move tables.
+impl SearchSpace for PruningTables {
+    type Node = CoordState;
+
+    fn apply_move(&self, node: &CoordState, index: usize) -> CoordState {
+        MOVE_TABLES.apply_move(node, index)
+    }
+
+    fn get_h_value(&self, node: &CoordState) -> u8 {
+        self.compute_h_value_of_coord(node)
+    }
+
+    fn is_goal(&self, node: &CoordState) -> bool {
+        *node == CoordState::solved()
+    }
+}
+
+impl SearchSpace for &dyn Goal {
+    type Node = CubeState;
+
+    fn apply_move(&self, node: &CubeState, index: usize) -> CubeState {
+        node.apply_move_instance(&ALL_MOVES[index])
+    }
+
+    fn get_h_value(&self, node: &CubeState) -> u8 {
+        Goal::get_h_value(*self, node)
+    }
+
+    fn is_goal(&self, node: &CubeState) -> bool {
+        self.is_reached(node)
+    }
+}
+
 impl<'a> IDASolver<'a> {
-    pub fn new(state: CubeState, tables: &'a PruningTables) -> Self {
+    pub fn new(state: impl Into<CubeState>, tables: &'a PruningTables) -> Self {
         Self {
-            start_state: state,
-            pruning_tables: tables,
+            start_state: state.into(),
+            tables,
         }
     }
 
-    fn search_for_solution(
-        &self,
-        mut curr_path: &mut MoveSequence,
-        last_state: &CubeState,
+    fn search_for_solution<T: SearchSpace>(
+        space: &T,
+        curr_path: &mut MoveSequence,
+        last_state: &T::Node,
         g: u8,
         bound: u8,
     ) -> SearchResult {
-        let last_h = self.pruning_tables.compute_h_value(&last_state);
-        let f = g + last_h;
+        let last_h = space.get_h_value(last_state);
+        let f = g.saturating_add(last_h);
         if f > bound {
             SearchResult::NewBound(f)
-        } else if *last_state == CubeState::default() {
+        } else if space.is_goal(last_state) {
             // yay it's solved!
             SearchResult::Found
         } else {
-            let mut min = std::u8::MAX;
-            let allowed_moves = allowed_moves_after_seq(&curr_path);
-            for m in ALL_MOVES
+            let mut min = u8::MAX;
+            let allowed_moves = allowed_moves_after_seq(curr_path);
+            for (i, m) in ALL_MOVES
                 .iter()
-                .filter(|mo| ((1 << get_basemove_pos(mo.basemove)) & allowed_moves) == 0)
+                .enumerate()
+                .filter(|(_, mo)| ((1 << get_basemove_pos(mo.basemove)) & allowed_moves) == 0)
             {
-                if curr_path.get_moves().len() > 0 {
+                if !curr_path.get_moves().is_empty() {
                     let path = curr_path.get_moves_mut();
                     let last_move = path[path.len() - 1];
                     if last_move.basemove == m.basemove {
@@ -149,8 +238,8 @@ impl<'a> IDASolver<'a> {
                     }
                 }
                 curr_path.get_moves_mut().push(*m);
-                let next_state = last_state.apply_move_instance(m);
-                let t = self.search_for_solution(&mut curr_path, &next_state, g + 1, bound);
+                let next_state = space.apply_move(last_state, i);
+                let t = Self::search_for_solution(space, curr_path, &next_state, g + 1, bound);
                 match t {
                     SearchResult::Found => return SearchResult::Found,
                     SearchResult::NewBound(b) => {
@@ -162,30 +251,249 @@ impl<'a> IDASolver<'a> {
             SearchResult::NewBound(min)
         }
     }
+
+    fn search<T: SearchSpace>(
+        space: &T,
+        start_state: &T::Node,
+        max_depth: u8,
+    ) -> Option<MoveSequence> {
+        // initial lower bound on number of moves needed to solve start state
+        let mut bound = space.get_h_value(start_state);
+        let mut path: MoveSequence = MoveSequence(vec![]);
+        while bound <= max_depth && bound < u8::MAX {
+            match Self::search_for_solution(space, &mut path, start_state, 0, bound) {
+                SearchResult::Found => {
+                    return Some(path);
+                }
+                SearchResult::NewBound(t) => {
+                    bound = t;
+                }
+            }
+        }
+        None
+    }
+
+    /// Finds an optimal solution of at most `max_depth` moves, besides the
+    /// rotation that brings the centers home, or returns `None` if there is
+    /// none.
+    pub fn solve_within(&self, max_depth: u8) -> Option<MoveSequence> {
+        let (rotation, state) = center_state(self.get_start_state());
+        let path = Self::search(self.tables, &CoordState::from_state(&state), max_depth)?;
+        Some(MoveSequence([rotation, path.0].concat()))
+    }
 }
 
+/// The largest number of face turns needed to solve any solvable state,
+/// and so to reach any goal that can be reached at all.
+const GODS_NUMBER: u8 = 20;
+
 impl Solver for IDASolver<'_> {
     fn get_start_state(&self) -> &CubeState {
         &self.start_state
     }
 
+    /// Panics if the starting state is not solvable; see `CubeState::validate`.
     fn solve(&self) -> MoveSequence {
-        let start_state = self.get_start_state();
+        self.solve_within(GODS_NUMBER)
+            .expect("the starting state is not solvable")
+    }
+}
 
-        // initial lower bound on number of moves needed to solve start state
-        let mut bound = self.pruning_tables.compute_h_value(&start_state);
-        let mut path: MoveSequence = MoveSequence(vec![]);
-        loop {
-            println!("Searching depth {}...", bound);
-            match self.search_for_solution(&mut path, &start_state, 0, bound) {
-                SearchResult::Found => {
-                    break;
-                }
-                SearchResult::NewBound(t) => {
-                    bound = t;
+impl<'a> GoalSolver<'a> {
+    pub fn new(state: impl Into<CubeState>, goal: &'a dyn Goal) -> Self {
+        Self {
+            start_state: state.into(),
+            goal,
+        }
+    }
+
+    /// Gets a reference to the starting configuration.
+    pub fn get_start_state(&self) -> &CubeState {
+        &self.start_state
+    }
+
+    /// Finds an optimal sequence of moves reaching the goal, starting with
+    /// the rotation that brings the centers home, or returns `None` if the
+    /// goal cannot be reached.
+    ///
+    /// A goal whose heuristic cannot tell that it is unreachable, as
+    /// `MaskedGoal` does, is searched up to God's number of moves before
+    /// giving up, which may take very long; see `solve_within`.
+    pub fn solve(&self) -> Option<MoveSequence> {
+        self.solve_within(GODS_NUMBER)
+    }
+
+    /// Finds an optimal sequence of at most `max_depth` moves reaching the
+    /// goal, besides the rotation that brings the centers home, or returns
+    /// `None` if there is none.
+    pub fn solve_within(&self, max_depth: u8) -> Option<MoveSequence> {
+        let (rotation, state) = center_state(self.get_start_state());
+        let path = IDASolver::search(&self.goal, &state, max_depth)?;
+        Some(MoveSequence([rotation, path.0].concat()))
+    }
+}
+
+/// The largest number of states a table of `MaskedGoal` may grow to before
+/// its search stops.
+const MASKED_TABLE_LIMIT: usize = 1 << 20;
+
+/// A goal given by a masked target state, usually the solved state under
+/// a mask, e.g. `MaskedState::solved(&PieceMask::cross())`.
+///
+/// The heuristic takes the larger of two tables, holding the distances to
+/// the target of its corners and of its edges. Each table is filled by a
+/// breadth-first search from the target, which stops early if the table
+/// grows too large, in which case the states not found are at least one
+/// move further than the last ones found. If the search finishes instead,
+/// the target cannot be reached from the states not found.
+pub struct MaskedGoal {
+    target: MaskedState,
+    tables: Vec<MaskedTable>,
+}
+
+/// The distances to a target of the states near it, identified by a key
+/// on part of their pieces.
+struct MaskedTable {
+    key: fn(&MaskedState) -> u128,
+    distances: HashMap<u128, u8>,
+    /// A lower bound on the distance of the keys not in the table, or
+    /// `u8::MAX` if they cannot reach the target.
+    bound: u8,
+}
+
+/// Packs labels and orientations into an integer, with 4 bits per label
+/// and 2 bits per orientation.
+fn pack_pieces(labels: &[Option<u8>], orientations: &[Option<i8>]) -> u128 {
+    labels
+        .iter()
+        .zip(orientations.iter())
+        .fold(0, |key, (label, orientation)| {
+            let label = label.map_or(15, |l| {
+                assert!(l < 15, "labels must be less than 15");
+                l
+            });
+            let orientation = orientation.map_or(3, |o| o.rem_euclid(3) as u8);
+            key << 6 | (label as u128) << 2 | orientation as u128
+        })
+}
+
+fn pack_corners(state: &MaskedState) -> u128 {
+    pack_pieces(&state.cp, &state.co)
+}
+
+fn pack_edges(state: &MaskedState) -> u128 {
+    pack_pieces(&state.ep, &state.eo)
+}
+
+impl MaskedTable {
+    /// Fills a table by a breadth-first search from the target.
+    fn new(target: &MaskedState, key: fn(&MaskedState) -> u128) -> Self {
+        let moves: Vec<CubeState> = ALL_MOVES
+            .iter()
+            .map(|m| CubeState::default().apply_move_instance(m))
+            .collect();
+        let mut distances = HashMap::new();
+        distances.insert(key(target), 0);
+        let mut frontier = vec![target.clone()];
+        let mut depth = 0;
+        while !frontier.is_empty() {
+            if distances.len() + frontier.len() * moves.len() > MASKED_TABLE_LIMIT {
+                break;
+            }
+            depth += 1;
+            let mut next = vec![];
+            for state in frontier.iter() {
+                for m in moves.iter() {
+                    let new_state = state.compose(m);
+                    if let Entry::Vacant(entry) = distances.entry(key(&new_state)) {
+                        entry.insert(depth);
+                        next.push(new_state);
+                    }
                 }
             }
+            frontier = next;
         }
-        path
+        // if the search finished, every state that reaches the target was found
+        let bound = if frontier.is_empty() {
+            u8::MAX
+        } else {
+            depth + 1
+        };
+        MaskedTable {
+            key,
+            distances,
+            bound,
+        }
+    }
+
+    fn get_h_value(&self, state: &MaskedState) -> u8 {
+        *self
+            .distances
+            .get(&(self.key)(state))
+            .unwrap_or(&self.bound)
+    }
+}
+
+impl MaskedGoal {
+    /// Builds the goal of reaching a masked state, with its heuristic.
+    ///
+    /// Panics if a label of the mask is 15 or more.
+    pub fn new(target: &MaskedState) -> Self {
+        let mask = target.get_mask();
+        let mut tables = vec![];
+        if mask.corners.iter().any(Option::is_some) || mask.corner_orientations.contains(&true) {
+            tables.push(MaskedTable::new(target, pack_corners));
+        }
+        if mask.edges.iter().any(Option::is_some) || mask.edge_orientations.contains(&true) {
+            tables.push(MaskedTable::new(target, pack_edges));
+        }
+        MaskedGoal {
+            target: target.clone(),
+            tables,
+        }
+    }
+}
+
+impl Goal for MaskedGoal {
+    fn is_reached(&self, state: &CubeState) -> bool {
+        self.target.matches(state)
+    }
+
+    fn get_h_value(&self, state: &CubeState) -> u8 {
+        let masked = MaskedState::new(state, self.target.get_mask());
+        self.tables
+            .iter()
+            .map(|table| table.get_h_value(&masked))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// A goal given by a predicate, e.g. one of the method-step predicates
+/// of `steps`, with the heuristic of another goal.
+///
+/// The heuristic is only admissible if every state satisfying the
+/// predicate also reaches the other goal.
+pub struct PredicateGoal<'a, F> {
+    predicate: F,
+    relaxation: &'a dyn Goal,
+}
+
+impl<'a, F: Fn(&CubeState) -> bool> PredicateGoal<'a, F> {
+    pub fn new(predicate: F, relaxation: &'a dyn Goal) -> Self {
+        PredicateGoal {
+            predicate,
+            relaxation,
+        }
+    }
+}
+
+impl<F: Fn(&CubeState) -> bool> Goal for PredicateGoal<'_, F> {
+    fn is_reached(&self, state: &CubeState) -> bool {
+        (self.predicate)(state)
+    }
+
+    fn get_h_value(&self, state: &CubeState) -> u8 {
+        self.relaxation.get_h_value(state)
     }
 }
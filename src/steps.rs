@@ -0,0 +1,314 @@
+//! A module for detecting the progress of a solve.
+//!
+//! The steps of the common methods are described by a `StepMask`: the
+//! pieces that must be solved, the positions whose pieces must be
+//! oriented, and the centers the pieces are judged against. For example:
+//!
+//! - CFOP: the cross, each F2L pair, the whole F2L, then OLL
+//! - Roux: the first block, the second block, then CMLL
+//! - ZZ and FMC: edge orientation (EO) on an axis, then domino reduction
+//!
+//! A step is checked relative to the centers, so that it does not matter
+//! how the cube is held, and the steps that can be solved on any side are
+//! checked on every side. The pieces are numbered as in `CubeState`, with
+//! the cross on D, the first block on L and the last layer on U.
+//!
+//! ```
+//! use rusty_rubik::cube::*;
+//! use rusty_rubik::parser::parse_scramble;
+//!
+//! fn main() {
+//!     // the B face is untouched, so its F2L is solved
+//!     let seq = MoveSequence(parse_scramble("x F R U R' U' F'").unwrap());
+//!     let state = CubeState::default().apply_move_instances(&seq);
+//!
+//!     assert!(state.is_f2l_solved());
+//!     assert!(!state.is_oll_solved());
+//! }
+//! ```
+
+use crate::cube::*;
+use crate::lastlayer::get_u_turn;
+use lazy_static::lazy_static;
+
+/// The axes of the cube, named after the faces they pass through.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum Axis {
+    UD,
+    LR,
+    FB,
+}
+
+/// A description of a step as a set of masks over the pieces of a state,
+/// where bit `i` of a mask stands for index `i` of the matching array.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct StepMask {
+    /// The corners that must be solved.
+    pub corners: u8,
+    /// The edges that must be solved.
+    pub edges: u16,
+    /// The corner positions whose pieces must be oriented.
+    pub oriented_corners: u8,
+    /// The edge positions whose pieces must be oriented.
+    pub oriented_edges: u16,
+    /// The edges that must stay within these positions, in any order.
+    pub separated_edges: u16,
+    /// The centers, in the order U, D, L, R, F, B, that the pieces are
+    /// judged against. Centers outside the mask may be moved, e.g. by the
+    /// M slice in Roux.
+    pub centers: u8,
+    /// Whether the U face may be turned, e.g. after CMLL.
+    pub auf: bool,
+}
+
+const ALL_CENTERS: u8 = 0b111111;
+const L_CENTER: u8 = 1 << 2;
+const R_CENTER: u8 = 1 << 3;
+
+lazy_static! {
+    /// The 24 rotations of the whole cube, starting with the identity.
+    static ref ROTATIONS: Vec<CubeState> = {
+        let x = MoveInstance::new(BaseMoveToken::X, Direction::Normal);
+        let y = MoveInstance::new(BaseMoveToken::Y, Direction::Normal);
+        let mut rotations = vec![CubeState::default()];
+        let mut i = 0;
+        while i < rotations.len() {
+            for m in [x, y].iter() {
+                let rotation = rotations[i].apply_move_instance(m);
+                if !rotations.contains(&rotation) {
+                    rotations.push(rotation);
+                }
+            }
+            i += 1;
+        }
+        rotations
+    };
+}
+
+fn is_set(mask: u16, i: usize) -> bool {
+    mask & (1 << i) != 0
+}
+
+impl StepMask {
+    /// The four edges of the D face.
+    pub const CROSS: Self = StepMask {
+        corners: 0,
+        edges: 0b1111_0000_0000,
+        oriented_corners: 0,
+        oriented_edges: 0,
+        separated_edges: 0,
+        centers: ALL_CENTERS,
+        auf: false,
+    };
+    /// The first two layers, below the U face.
+    pub const F2L: Self = StepMask {
+        corners: 0b1111_0000,
+        edges: 0b1111_1111_0000,
+        ..StepMask::CROSS
+    };
+    /// The first two layers, with the U face oriented.
+    pub const OLL: Self = StepMask {
+        oriented_corners: 0b1111,
+        oriented_edges: 0b1111,
+        ..StepMask::F2L
+    };
+    /// Every edge oriented relative to the F/B axis.
+    pub const EO: Self = StepMask {
+        oriented_edges: 0b1111_1111_1111,
+        ..StepMask::NONE
+    };
+    /// The 1x2x3 block on the left, judged against the L center.
+    pub const FIRST_BLOCK: Self = StepMask {
+        corners: 0b1001_0000,
+        edges: 0b1000_1001_0000,
+        centers: L_CENTER,
+        ..StepMask::NONE
+    };
+    /// Both 1x2x3 blocks, judged against the L and R centers.
+    pub const SECOND_BLOCK: Self = StepMask {
+        corners: 0b1111_0000,
+        edges: 0b1010_1111_0000,
+        centers: L_CENTER | R_CENTER,
+        ..StepMask::NONE
+    };
+    /// Both 1x2x3 blocks and the corners of the U face, which may be turned.
+    pub const CMLL: Self = StepMask {
+        corners: 0b1111_1111,
+        auf: true,
+        ..StepMask::SECOND_BLOCK
+    };
+    /// Every piece oriented relative to the U/D axis, with the edges of
+    /// the E slice in the E slice, so that the cube can be solved with
+    /// U, D and half turns.
+    pub const DOMINO: Self = StepMask {
+        oriented_corners: 0b1111_1111,
+        separated_edges: 0b1111_0000,
+        ..StepMask::EO
+    };
+    /// Every piece solved.
+    pub const SOLVED: Self = StepMask {
+        corners: 0b1111_1111,
+        edges: 0b1111_1111_1111,
+        ..StepMask::NONE
+    };
+
+    const NONE: Self = StepMask {
+        corners: 0,
+        edges: 0,
+        oriented_corners: 0,
+        oriented_edges: 0,
+        separated_edges: 0,
+        centers: ALL_CENTERS,
+        auf: false,
+    };
+
+    /// Returns the mask of the cross and one F2L pair, numbered by its
+    /// corner from 0 to 3: DFL, DFR, DBR, then DBL.
+    ///
+    /// Panics if the pair is not one of these.
+    pub fn f2l_pair(pair: usize) -> Self {
+        assert!(pair < 4, "there are only 4 F2L pairs");
+        // the edge above the n-th corner of the D face is edge 7 - n
+        StepMask {
+            corners: 1 << (4 + pair),
+            edges: StepMask::CROSS.edges | 1 << (7 - pair),
+            ..StepMask::CROSS
+        }
+    }
+
+    /// Returns `true` if a state with its centers in their home positions
+    /// matches this mask.
+    fn matches(&self, state: &CubeState) -> bool {
+        let corners = self.corners as u16;
+        let oriented_corners = self.oriented_corners as u16;
+        (0..8).all(|i| !is_set(corners, i) || (state.cp[i] == i as u8 && state.co[i] == 0))
+            && (0..12)
+                .all(|i| !is_set(self.edges, i) || (state.ep[i] == i as u8 && state.eo[i] == 0))
+            && (0..8).all(|i| !is_set(oriented_corners, i) || state.co[i] == 0)
+            && (0..12).all(|i| !is_set(self.oriented_edges, i) || state.eo[i] == 0)
+            && (0..12).all(|i| {
+                !is_set(self.separated_edges, i)
+                    || is_set(self.separated_edges, state.ep[i] as usize)
+            })
+    }
+
+    /// Returns `true` if this step is solved in a state, relative to the
+    /// centers in the mask, however the cube is held.
+    pub fn is_solved(&self, state: &CubeState) -> bool {
+        let aufs = if self.auf { 4 } else { 1 };
+        // a rotation after the state moves the centers back into place,
+        // along with the pieces around them
+        ROTATIONS.iter().any(|rotation| {
+            (0..6).all(|i| {
+                !is_set(self.centers as u16, i)
+                    || state.centers[rotation.centers[i] as usize] == i as u8
+            }) && {
+                let state = state.compose(rotation);
+                (0..aufs).any(|turns| self.matches(&state.compose(&get_u_turn(turns))))
+            }
+        })
+    }
+
+    /// Returns `true` if this step is solved in a state on any side of
+    /// the cube, e.g. the cross of any color.
+    pub fn is_solved_on_any_side(&self, state: &CubeState) -> bool {
+        // a rotation before the state relabels the pieces by their new sides
+        ROTATIONS
+            .iter()
+            .any(|rotation| self.is_solved(&rotation.compose(state)))
+    }
+}
+
+/// Returns a state with its pieces relabeled so that the given axis
+/// becomes the F/B axis.
+fn view_along(state: &CubeState, axis: Axis) -> CubeState {
+    let rotation = match axis {
+        Axis::UD => BaseMoveToken::X,
+        Axis::LR => BaseMoveToken::Y,
+        Axis::FB => return state.clone(),
+    };
+    CubeState::default()
+        .apply_move_instance(&MoveInstance::new(rotation, Direction::Normal))
+        .compose(state)
+}
+
+impl CubeState {
+    /// Returns `true` if every piece is solved relative to the centers.
+    ///
+    /// Unlike `is_identity`, the cube may be held in any orientation.
+    pub fn is_solved(&self) -> bool {
+        StepMask::SOLVED.is_solved(self)
+    }
+
+    /// Returns `true` if the cross of any color is solved.
+    pub fn is_cross_solved(&self) -> bool {
+        StepMask::CROSS.is_solved_on_any_side(self)
+    }
+
+    /// Returns `true` if the D cross and the given F2L pair are solved,
+    /// with the pairs numbered as in `StepMask::f2l_pair`.
+    ///
+    /// Panics if the pair is not between 0 and 3.
+    pub fn is_f2l_pair_solved(&self, pair: usize) -> bool {
+        StepMask::f2l_pair(pair).is_solved(self)
+    }
+
+    /// Returns the number of F2L pairs solved along with a cross, taking
+    /// the cross with the most pairs if several are solved.
+    pub fn count_solved_f2l_pairs(&self) -> usize {
+        ROTATIONS
+            .iter()
+            .map(|rotation| rotation.compose(self))
+            .filter(|state| StepMask::CROSS.is_solved(state))
+            .map(|state| {
+                (0..4)
+                    .filter(|&pair| state.is_f2l_pair_solved(pair))
+                    .count()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns `true` if the first two layers are solved on any side.
+    ///
+    /// Unlike `lastlayer::is_f2l_solved`, the last layer may be any face.
+    pub fn is_f2l_solved(&self) -> bool {
+        StepMask::F2L.is_solved_on_any_side(self)
+    }
+
+    /// Returns `true` if the first two layers are solved on any side and
+    /// the last layer is oriented.
+    pub fn is_oll_solved(&self) -> bool {
+        StepMask::OLL.is_solved_on_any_side(self)
+    }
+
+    /// Returns `true` if every edge is oriented relative to an axis,
+    /// i.e. the edges can be solved without quarter turns of the two
+    /// faces on the axis.
+    pub fn is_eo_solved(&self, axis: Axis) -> bool {
+        StepMask::EO.is_solved(&view_along(self, axis))
+    }
+
+    /// Returns `true` if a Roux first block is solved on any side.
+    pub fn is_first_block_solved(&self) -> bool {
+        StepMask::FIRST_BLOCK.is_solved_on_any_side(self)
+    }
+
+    /// Returns `true` if both Roux blocks are solved on any side.
+    pub fn is_second_block_solved(&self) -> bool {
+        StepMask::SECOND_BLOCK.is_solved_on_any_side(self)
+    }
+
+    /// Returns `true` if both Roux blocks and the corners between them are
+    /// solved on any side, up to a turn of the remaining face.
+    pub fn is_cmll_solved(&self) -> bool {
+        StepMask::CMLL.is_solved_on_any_side(self)
+    }
+
+    /// Returns `true` if the cube is in domino reduction on any axis,
+    /// i.e. it can be solved with quarter turns of the two faces on the
+    /// axis and half turns of the others.
+    pub fn is_domino_reduced(&self) -> bool {
+        StepMask::DOMINO.is_solved_on_any_side(self)
+    }
+}
@@ -0,0 +1,141 @@
+//! A module for generating SVG images of the Rubik's Cube.
+//!
+//! Two kinds of images are supported:
+//!
+//! - An unfolded net of the whole cube, laid out like WCA scramble images.
+//! - A top view of the last layer, showing the U face surrounded by the top
+//!   row of stickers of each side face, as is common in OLL and PLL diagrams.
+//!
+//! The images are returned as strings, and can be written directly to `.svg` files.
+
+use crate::cube::{CubeState, MoveSequence};
+use crate::facelet::Face;
+
+/// The fill colors used for the stickers of each face, in facelet order
+/// (U, R, F, D, L, B). Any SVG color is allowed, such as `#ff0000` or `red`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SvgColorScheme(pub [String; 6]);
+
+impl Default for SvgColorScheme {
+    /// The color scheme used in WCA scramble images.
+    fn default() -> Self {
+        SvgColorScheme([
+            String::from("#ffffff"),
+            String::from("#ff0000"),
+            String::from("#00ff00"),
+            String::from("#ffff00"),
+            String::from("#ffa500"),
+            String::from("#0000ff"),
+        ])
+    }
+}
+
+impl SvgColorScheme {
+    /// Returns the fill color of the stickers of a face.
+    pub fn get_color(&self, face: Face) -> &str {
+        &self.0[face as usize]
+    }
+}
+
+/// The side length of a sticker, in pixels.
+const STICKER_SIZE: usize = 20;
+/// The space between two faces of the net, in pixels.
+const FACE_GAP: usize = 4;
+/// The thickness of the side stickers in the last layer view, in pixels.
+const SIDE_STICKER_SIZE: usize = 8;
+
+fn svg_document(width: usize, height: usize, body: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n{}</svg>\n",
+        body,
+        w = width,
+        h = height,
+    )
+}
+
+fn svg_rect(x: usize, y: usize, width: usize, height: usize, fill: &str) -> String {
+    format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#000000\" stroke-width=\"1\"/>\n",
+        x, y, width, height, fill
+    )
+}
+
+/// Renders the unfolded net of a state as an SVG image.
+pub fn render_net_svg(state: &CubeState, colors: &SvgColorScheme) -> String {
+    let facelets = state.to_facelets();
+    let face_size = 3 * STICKER_SIZE + FACE_GAP;
+    // the position of each face in the net, in units of faces, in facelet order
+    let positions = [(1, 0), (2, 1), (1, 1), (1, 2), (0, 1), (3, 1)];
+    let mut body = String::new();
+    for (i, (col, row)) in positions.iter().enumerate() {
+        for j in 0..9 {
+            let x = FACE_GAP + col * face_size + (j % 3) * STICKER_SIZE;
+            let y = FACE_GAP + row * face_size + (j / 3) * STICKER_SIZE;
+            let fill = colors.get_color(facelets[9 * i + j]);
+            body.push_str(&svg_rect(x, y, STICKER_SIZE, STICKER_SIZE, fill));
+        }
+    }
+    svg_document(FACE_GAP + 4 * face_size, FACE_GAP + 3 * face_size, &body)
+}
+
+/// Renders the unfolded net of the state obtained by applying a scramble
+/// to the solved state, as an SVG image.
+pub fn render_scramble_svg(scramble: &MoveSequence, colors: &SvgColorScheme) -> String {
+    render_net_svg(&CubeState::default().apply_move_instances(scramble), colors)
+}
+
+/// Renders a top view of the last layer of a state as an SVG image.
+///
+/// The U face is drawn in the middle, and the top row of stickers of each
+/// side face is drawn next to the U face edge it touches.
+pub fn render_last_layer_svg(state: &CubeState, colors: &SvgColorScheme) -> String {
+    let facelets = state.to_facelets();
+    let offset = FACE_GAP + SIDE_STICKER_SIZE + FACE_GAP;
+    let mut body = String::new();
+    for (j, face) in facelets.iter().take(9).enumerate() {
+        let x = offset + (j % 3) * STICKER_SIZE;
+        let y = offset + (j / 3) * STICKER_SIZE;
+        let fill = colors.get_color(*face);
+        body.push_str(&svg_rect(x, y, STICKER_SIZE, STICKER_SIZE, fill));
+    }
+    let far = offset + 3 * STICKER_SIZE + FACE_GAP;
+    for k in 0..3 {
+        let along = offset + k * STICKER_SIZE;
+        // B is seen from behind, so its top row runs from right to left
+        let back = colors.get_color(facelets[45 + 2 - k]);
+        body.push_str(&svg_rect(
+            along,
+            FACE_GAP,
+            STICKER_SIZE,
+            SIDE_STICKER_SIZE,
+            back,
+        ));
+        let front = colors.get_color(facelets[18 + k]);
+        body.push_str(&svg_rect(
+            along,
+            far,
+            STICKER_SIZE,
+            SIDE_STICKER_SIZE,
+            front,
+        ));
+        let left = colors.get_color(facelets[36 + k]);
+        body.push_str(&svg_rect(
+            FACE_GAP,
+            along,
+            SIDE_STICKER_SIZE,
+            STICKER_SIZE,
+            left,
+        ));
+        // R is seen from the right, so its top row runs from front to back
+        let right = colors.get_color(facelets[9 + 2 - k]);
+        body.push_str(&svg_rect(
+            far,
+            along,
+            SIDE_STICKER_SIZE,
+            STICKER_SIZE,
+            right,
+        ));
+    }
+    let size = far + SIDE_STICKER_SIZE + FACE_GAP;
+    svg_document(size, size, &body)
+}
@@ -0,0 +1,452 @@
+//! A module for the spatial symmetries of the Rubik's Cube.
+//!
+//! The cube has 48 symmetries: the 24 rotations of the whole cube, each
+//! optionally combined with a reflection. Each symmetry is represented by
+//! the orthogonal matrix acting on the coordinates of the cube, where the
+//! x axis points from L to R, the y axis from D to U and the z axis from B to F.
+//!
+//! Conjugating a state by a symmetry gives the state obtained by performing
+//! the transformed moves on a solved cube, so that for any sequence of
+//! moves $A$, conjugating the state reached by $A$ gives the state reached
+//! by the conjugated sequence. For reflections, this is the mirrored algorithm.
+
+use crate::cube::*;
+use crate::facelet::{
+    get_corner_of_colors, get_edge_of_colors, Face, ALL_FACES, CENTER_FACES, CORNER_COLORS,
+    CORNER_FACELETS, EDGE_COLORS, EDGE_FACELETS,
+};
+use lazy_static::lazy_static;
+
+/// A spatial symmetry of the Rubik's Cube.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct Symmetry {
+    matrix: [[i8; 3]; 3],
+}
+
+/// The face relabeling induced by a symmetry, along with its action on
+/// the pieces of a `CubeState`, worked out from its action on the facelets.
+struct SymmetryAction {
+    face_map: [Face; 6],
+    /// The position, piece and orientation that each piece and
+    /// orientation at each corner position is sent to.
+    corners: [[[(u8, u8, i8); 3]; 8]; 8],
+    /// The position, piece and orientation that each piece and
+    /// orientation at each edge position is sent to.
+    edges: [[[(u8, u8, i8); 2]; 12]; 12],
+    /// The index that each center is sent to, in the order of `CubeState`.
+    centers: [u8; 6],
+}
+
+lazy_static! {
+    static ref ALL_SYMMETRIES: Vec<Symmetry> = {
+        let perms = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+        let mut syms = vec![];
+        for perm in perms.iter() {
+            for signs in 0..8 {
+                let mut matrix = [[0; 3]; 3];
+                for (row, col) in perm.iter().enumerate() {
+                    matrix[row][*col] = if signs & (1 << row) == 0 { 1 } else { -1 };
+                }
+                syms.push(Symmetry { matrix });
+            }
+        }
+        // rotations come first, with the identity leading
+        syms.sort_by_key(|s| (s.is_reflection(), *s != Symmetry::identity()));
+        syms
+    };
+    static ref SYMMETRY_ACTIONS: Vec<SymmetryAction> = ALL_SYMMETRIES
+        .iter()
+        .map(|sym| {
+            let mut facelet_perm = [0; 54];
+            for (i, p) in facelet_perm.iter_mut().enumerate() {
+                let (pos, normal) = get_facelet_geometry(i);
+                *p = get_facelet_index(sym.apply(pos), sym.apply(normal));
+            }
+            let face_map = ALL_FACES.map(|f| get_face_of_normal(sym.apply(get_normal(f))));
+            let mut corners = [[[(0, 0, 0); 3]; 8]; 8];
+            for (pos, pieces) in corners.iter_mut().enumerate() {
+                for (piece, oris) in pieces.iter_mut().enumerate() {
+                    for (ori, image) in oris.iter_mut().enumerate() {
+                        // move the stickers of the piece, then read them back
+                        let mut stickers = [(0, Face::U); 3];
+                        for (k, sticker) in stickers.iter_mut().enumerate() {
+                            let facelet = CORNER_FACELETS[pos][(k + ori) % 3];
+                            let color = CORNER_COLORS[piece][k];
+                            *sticker = (facelet_perm[facelet], face_map[color as usize]);
+                        }
+                        let new_pos = get_position_of_facelet(&CORNER_FACELETS, stickers[0].0);
+                        let colors = CORNER_FACELETS[new_pos]
+                            .map(|f| stickers.iter().find(|s| s.0 == f).unwrap().1);
+                        let (new_piece, new_ori) = get_corner_of_colors(colors).unwrap();
+                        *image = (new_pos as u8, new_piece, new_ori);
+                    }
+                }
+            }
+            let mut edges = [[[(0, 0, 0); 2]; 12]; 12];
+            for (pos, pieces) in edges.iter_mut().enumerate() {
+                for (piece, oris) in pieces.iter_mut().enumerate() {
+                    for (ori, image) in oris.iter_mut().enumerate() {
+                        let mut stickers = [(0, Face::U); 2];
+                        for (k, sticker) in stickers.iter_mut().enumerate() {
+                            let facelet = EDGE_FACELETS[pos][(k + ori) % 2];
+                            let color = EDGE_COLORS[piece][k];
+                            *sticker = (facelet_perm[facelet], face_map[color as usize]);
+                        }
+                        let new_pos = get_position_of_facelet(&EDGE_FACELETS, stickers[0].0);
+                        let colors = EDGE_FACELETS[new_pos]
+                            .map(|f| stickers.iter().find(|s| s.0 == f).unwrap().1);
+                        let (new_piece, new_ori) = get_edge_of_colors(colors).unwrap();
+                        *image = (new_pos as u8, new_piece, new_ori);
+                    }
+                }
+            }
+            let centers = CENTER_FACES.map(|f| {
+                let image = face_map[f as usize];
+                CENTER_FACES.iter().position(|&g| g == image).unwrap() as u8
+            });
+            SymmetryAction {
+                face_map,
+                corners,
+                edges,
+                centers,
+            }
+        })
+        .collect();
+}
+
+/// Returns the position of a piece that a facelet lies on.
+fn get_position_of_facelet<const N: usize>(positions: &[[usize; N]], facelet: usize) -> usize {
+    positions.iter().position(|p| p.contains(&facelet)).unwrap()
+}
+
+fn get_normal(face: Face) -> [i8; 3] {
+    match face {
+        Face::U => [0, 1, 0],
+        Face::R => [1, 0, 0],
+        Face::F => [0, 0, 1],
+        Face::D => [0, -1, 0],
+        Face::L => [-1, 0, 0],
+        Face::B => [0, 0, -1],
+    }
+}
+
+fn get_face_of_normal(normal: [i8; 3]) -> Face {
+    *ALL_FACES
+        .iter()
+        .find(|f| get_normal(**f) == normal)
+        .unwrap()
+}
+
+/// Returns the position of the cubie a facelet lies on, and the
+/// outward normal of the facelet.
+fn get_facelet_geometry(index: usize) -> ([i8; 3], [i8; 3]) {
+    let face = ALL_FACES[index / 9];
+    let row = (index % 9 / 3) as i8;
+    let col = (index % 3) as i8;
+    // faces are read left-to-right, top-to-bottom as seen from outside
+    let pos = match face {
+        Face::U => [col - 1, 1, row - 1],
+        Face::R => [1, 1 - row, 1 - col],
+        Face::F => [col - 1, 1 - row, 1],
+        Face::D => [col - 1, -1, 1 - row],
+        Face::L => [-1, 1 - row, col - 1],
+        Face::B => [1 - col, 1 - row, -1],
+    };
+    (pos, get_normal(face))
+}
+
+fn get_facelet_index(pos: [i8; 3], normal: [i8; 3]) -> usize {
+    (0..54)
+        .find(|i| get_facelet_geometry(*i) == (pos, normal))
+        .unwrap()
+}
+
+fn get_face_token(face: Face) -> BaseMoveToken {
+    match face {
+        Face::U => BaseMoveToken::U,
+        Face::R => BaseMoveToken::R,
+        Face::F => BaseMoveToken::F,
+        Face::D => BaseMoveToken::D,
+        Face::L => BaseMoveToken::L,
+        Face::B => BaseMoveToken::B,
+    }
+}
+
+fn get_wide_token(face: Face) -> BaseMoveToken {
+    match face {
+        Face::U => BaseMoveToken::Uw,
+        Face::R => BaseMoveToken::Rw,
+        Face::F => BaseMoveToken::Fw,
+        Face::D => BaseMoveToken::Dw,
+        Face::L => BaseMoveToken::Lw,
+        Face::B => BaseMoveToken::Bw,
+    }
+}
+
+fn get_opposite_face(face: Face) -> Face {
+    get_face_of_normal(get_normal(face).map(|x| -x))
+}
+
+impl Symmetry {
+    /// Returns the identity symmetry.
+    pub fn identity() -> Self {
+        Symmetry {
+            matrix: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+        }
+    }
+
+    /// Returns all 48 symmetries of the cube. The 24 rotations come first,
+    /// starting with the identity, followed by the 24 reflections.
+    pub fn all() -> &'static [Symmetry] {
+        &ALL_SYMMETRIES
+    }
+
+    /// Returns the 24 rotational symmetries of the cube.
+    pub fn rotations() -> &'static [Symmetry] {
+        &ALL_SYMMETRIES[..24]
+    }
+
+    /// Returns the rotation of the whole cube performed by a move.
+    ///
+    /// Transforming the state reached by a sequence $A$ by the rotation
+    /// of a move $r$ gives the state reached by $r^{-1} A r$.
+    /// Returns `None` if the move is not a whole-cube rotation.
+    pub fn from_rotation(m: &MoveInstance) -> Option<Self> {
+        let quarter = match m.basemove {
+            BaseMoveToken::X => [[1, 0, 0], [0, 0, 1], [0, -1, 0]],
+            BaseMoveToken::Y => [[0, 0, -1], [0, 1, 0], [1, 0, 0]],
+            BaseMoveToken::Z => [[0, 1, 0], [-1, 0, 0], [0, 0, 1]],
+            _ => return None,
+        };
+        let quarter = Symmetry { matrix: quarter };
+        let mut sym = Symmetry::identity();
+        for _ in 0..m.dir.get_num_turns() {
+            sym = quarter.compose(&sym);
+        }
+        Some(sym)
+    }
+
+    /// Returns the reflection through the plane between L and R, which
+    /// mirrors algorithms from the right hand to the left hand.
+    pub fn mirror_lr() -> Self {
+        Symmetry {
+            matrix: [[-1, 0, 0], [0, 1, 0], [0, 0, 1]],
+        }
+    }
+
+    /// Returns the reflection through the plane between U and D.
+    pub fn mirror_ud() -> Self {
+        Symmetry {
+            matrix: [[1, 0, 0], [0, -1, 0], [0, 0, 1]],
+        }
+    }
+
+    /// Returns the reflection through the plane between F and B.
+    pub fn mirror_fb() -> Self {
+        Symmetry {
+            matrix: [[1, 0, 0], [0, 1, 0], [0, 0, -1]],
+        }
+    }
+
+    /// Returns `true` if this symmetry is a reflection, i.e. it reverses
+    /// the handedness of the cube.
+    pub fn is_reflection(&self) -> bool {
+        let m = &self.matrix;
+        let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+        det < 0
+    }
+
+    /// Returns the symmetry obtained by applying `other` first, then `self`.
+    pub fn compose(&self, other: &Symmetry) -> Self {
+        let mut matrix = [[0; 3]; 3];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = (0..3).map(|k| self.matrix[i][k] * other.matrix[k][j]).sum();
+            }
+        }
+        Symmetry { matrix }
+    }
+
+    /// Returns the inverse of this symmetry.
+    pub fn invert(&self) -> Self {
+        // orthogonal matrices are inverted by transposing
+        let mut matrix = [[0; 3]; 3];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = self.matrix[j][i];
+            }
+        }
+        Symmetry { matrix }
+    }
+
+    fn apply(&self, v: [i8; 3]) -> [i8; 3] {
+        let mut result = [0; 3];
+        for (i, r) in result.iter_mut().enumerate() {
+            *r = (0..3).map(|k| self.matrix[i][k] * v[k]).sum();
+        }
+        result
+    }
+
+    fn get_action(&self) -> &'static SymmetryAction {
+        let index = ALL_SYMMETRIES.iter().position(|s| s == self).unwrap();
+        &SYMMETRY_ACTIONS[index]
+    }
+
+    /// Returns the face that the given face is sent to by this symmetry.
+    pub fn map_face(&self, face: Face) -> Face {
+        self.get_action().face_map[face as usize]
+    }
+
+    /// Transforms a single move by this symmetry.
+    pub fn conjugate_move(&self, m: &MoveInstance) -> MoveInstance {
+        // slices and rotations turn like a reference face
+        let (face, kind) = match m.basemove {
+            BaseMoveToken::U => (Face::U, 0),
+            BaseMoveToken::D => (Face::D, 0),
+            BaseMoveToken::L => (Face::L, 0),
+            BaseMoveToken::R => (Face::R, 0),
+            BaseMoveToken::F => (Face::F, 0),
+            BaseMoveToken::B => (Face::B, 0),
+            BaseMoveToken::Uw => (Face::U, 1),
+            BaseMoveToken::Dw => (Face::D, 1),
+            BaseMoveToken::Lw => (Face::L, 1),
+            BaseMoveToken::Rw => (Face::R, 1),
+            BaseMoveToken::Fw => (Face::F, 1),
+            BaseMoveToken::Bw => (Face::B, 1),
+            BaseMoveToken::M => (Face::L, 2),
+            BaseMoveToken::E => (Face::D, 2),
+            BaseMoveToken::S => (Face::F, 2),
+            BaseMoveToken::X => (Face::R, 3),
+            BaseMoveToken::Y => (Face::U, 3),
+            BaseMoveToken::Z => (Face::F, 3),
+        };
+        let image = self.map_face(face);
+        let (basemove, flipped) = match kind {
+            0 => (get_face_token(image), false),
+            1 => (get_wide_token(image), false),
+            2 => {
+                let slice = |f| match f {
+                    Face::L => Some(BaseMoveToken::M),
+                    Face::D => Some(BaseMoveToken::E),
+                    Face::F => Some(BaseMoveToken::S),
+                    _ => None,
+                };
+                match slice(image) {
+                    Some(b) => (b, false),
+                    None => (slice(get_opposite_face(image)).unwrap(), true),
+                }
+            }
+            _ => {
+                let rotation = |f| match f {
+                    Face::R => Some(BaseMoveToken::X),
+                    Face::U => Some(BaseMoveToken::Y),
+                    Face::F => Some(BaseMoveToken::Z),
+                    _ => None,
+                };
+                match rotation(image) {
+                    Some(b) => (b, false),
+                    None => (rotation(get_opposite_face(image)).unwrap(), true),
+                }
+            }
+        };
+        // turning the opposite way, or in a mirror, inverts the direction
+        let mov = MoveInstance::new(basemove, m.dir);
+        if flipped != self.is_reflection() {
+            mov.invert()
+        } else {
+            mov
+        }
+    }
+
+    /// Transforms a sequence of moves by this symmetry.
+    pub fn conjugate_sequence(&self, moves: &MoveSequence) -> MoveSequence {
+        MoveSequence(
+            moves
+                .get_moves()
+                .iter()
+                .map(|m| self.conjugate_move(m))
+                .collect(),
+        )
+    }
+
+    /// Transforms a state by this symmetry.
+    ///
+    /// The state need not be solvable, but its arrays must hold valid
+    /// piece numbers, or this panics.
+    pub fn conjugate_state(&self, state: &CubeState) -> CubeState {
+        let action = self.get_action();
+        let mut result = state.clone();
+        for pos in 0..8 {
+            let ori = state.co[pos].rem_euclid(3) as usize;
+            let (new_pos, piece, new_ori) = action.corners[pos][state.cp[pos] as usize][ori];
+            result.cp[new_pos as usize] = piece;
+            result.co[new_pos as usize] = new_ori;
+        }
+        for pos in 0..12 {
+            let ori = state.eo[pos].rem_euclid(2) as usize;
+            let (new_pos, piece, new_ori) = action.edges[pos][state.ep[pos] as usize][ori];
+            result.ep[new_pos as usize] = piece;
+            result.eo[new_pos as usize] = new_ori;
+        }
+        for pos in 0..6 {
+            result.centers[action.centers[pos] as usize] =
+                action.centers[state.centers[pos] as usize];
+        }
+        result
+    }
+}
+
+impl CubeState {
+    /// Transforms this state by a symmetry.
+    pub fn conjugate_by(&self, sym: &Symmetry) -> Self {
+        sym.conjugate_state(self)
+    }
+
+    /// Returns the distinct states obtained by transforming this state
+    /// by each of the 48 symmetries, in ascending order.
+    pub fn get_symmetry_class(&self) -> Vec<CubeState> {
+        let mut class: Vec<CubeState> = Symmetry::all()
+            .iter()
+            .map(|s| self.conjugate_by(s))
+            .collect();
+        class.sort();
+        class.dedup();
+        class
+    }
+
+    /// Returns the symmetries that leave this state unchanged.
+    pub fn get_self_symmetries(&self) -> Vec<Symmetry> {
+        Symmetry::all()
+            .iter()
+            .filter(|s| self.conjugate_by(s) == *self)
+            .copied()
+            .collect()
+    }
+
+    /// Returns the canonical representative of this state under symmetry,
+    /// i.e. the smallest state in its symmetry class. Two states are
+    /// equivalent under symmetry if and only if their representatives are equal.
+    pub fn get_canonical_representative(&self) -> CubeState {
+        Symmetry::all()
+            .iter()
+            .map(|s| self.conjugate_by(s))
+            .min()
+            .unwrap()
+    }
+}
+
+impl MoveSequence {
+    /// Transforms this sequence by a symmetry.
+    pub fn conjugate_by(&self, sym: &Symmetry) -> Self {
+        sym.conjugate_sequence(self)
+    }
+}
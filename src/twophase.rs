@@ -0,0 +1,367 @@
+//! A module for solving the Rubik's Cube quickly with Kociemba's
+//! two-phase algorithm.
+//!
+//! The first phase brings a state into the subgroup generated by U, D,
+//! L2, R2, F2 and B2, where every piece is oriented and the edges of the
+//! E slice are in the E slice. The second phase solves the state using
+//! only those moves. Each phase is an IDA* search on coordinates, pruned
+//! by tables small enough to be built in memory the first time they are
+//! used, so unlike `IDASolver`, no pruning table files are needed.
+//!
+//! The first phase is solved optimally, then the second phase optimally
+//! from where the first one ends. The solutions found are not optimal,
+//! usually between 20 and 30 moves, but are found in a fraction of a
+//! second, which makes this solver suitable for random-state scrambles.
+//!
+//! ```
+//! use rusty_rubik::cube::*;
+//! use rusty_rubik::solver::Solver;
+//! use rusty_rubik::twophase::TwoPhaseSolver;
+//!
+//! fn main() {
+//!     let state = CubeState::random_with_seed(1);
+//!     let solution = TwoPhaseSolver::new(state.clone()).solve();
+//!     assert!(solution.get_moves().len() <= 30);
+//!     assert_eq!(state.apply_move_instances(&solution), CubeState::default());
+//! }
+//! ```
+
+use crate::coord::*;
+use crate::cube::*;
+use crate::solver::{center_state, Solver};
+use lazy_static::lazy_static;
+
+/// The number of values of the coordinate of the set of positions of the
+/// E slice edges.
+const N_SLICE: usize = N_EDGE_SETS;
+/// The number of values of the permutation of the edges outside the E slice.
+const N_UD_EDGES: usize = 40320;
+/// The number of values of the permutation of the E slice edges.
+const N_SLICE_PERM: usize = 24;
+
+/// The indices in `ALL_MOVES` of the moves of the second phase.
+const PHASE_2_MOVES: [usize; 10] = [0, 1, 2, 3, 4, 5, 8, 11, 14, 17];
+/// The edge positions outside the E slice.
+const UD_EDGES: [usize; 8] = [0, 1, 2, 3, 8, 9, 10, 11];
+
+/// Every state reaches the subgroup of the second phase within 12 moves,
+/// and is solved from there within 18.
+const MAX_PHASE_1_LENGTH: u8 = 12;
+const MAX_PHASE_2_LENGTH: u8 = 18;
+
+lazy_static! {
+    static ref TABLES: TwoPhaseTables = TwoPhaseTables::new();
+}
+
+/// The move and pruning tables of both phases, beyond the shared move
+/// tables of `MOVE_TABLES`.
+struct TwoPhaseTables {
+    slice: Vec<[u16; 18]>,
+    ud_edges: Vec<[u16; 10]>,
+    slice_perm: Vec<[u16; 10]>,
+    twist_slice_pruning: Vec<u8>,
+    flip_slice_pruning: Vec<u8>,
+    corners_slice_pruning: Vec<u8>,
+    edges_slice_pruning: Vec<u8>,
+}
+
+/// Fills a pruning table by a breadth-first search from the goal, given
+/// the index reached by each move from each index.
+fn fill_pruning_table(
+    size: usize,
+    goal: usize,
+    moves: usize,
+    next: &dyn Fn(usize, usize) -> usize,
+) -> Vec<u8> {
+    let mut table = vec![u8::MAX; size];
+    table[goal] = 0;
+    let mut frontier = vec![goal];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next_frontier = vec![];
+        for &index in frontier.iter() {
+            for m in 0..moves {
+                let new_index = next(index, m);
+                if table[new_index] == u8::MAX {
+                    table[new_index] = depth;
+                    next_frontier.push(new_index);
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+    table
+}
+
+/// Returns the edge permutation with the given ranks of the permutations
+/// of the edges outside and inside the E slice.
+fn get_edges_of_indices(ud_edges: u16, slice_perm: u16) -> [u8; 12] {
+    let mut ep = [0; 12];
+    let ud_perm = get_permutation_of_index(ud_edges as u32, 8);
+    for (&pos, &k) in UD_EDGES.iter().zip(ud_perm.iter()) {
+        ep[pos] = UD_EDGES[k as usize] as u8;
+    }
+    let slice = get_permutation_of_index(slice_perm as u32, 4);
+    for (k, &p) in slice.iter().enumerate() {
+        ep[4 + k] = 4 + p;
+    }
+    ep
+}
+
+/// Returns the ranks of the permutations of the edges outside and inside
+/// the E slice, for a state whose E slice edges are in the E slice.
+fn get_indices_of_edges(ep: &[u8; 12]) -> (u16, u16) {
+    let mut ud_perm = [0; 8];
+    for (k, &pos) in UD_EDGES.iter().enumerate() {
+        ud_perm[k] = UD_EDGES.iter().position(|&p| p as u8 == ep[pos]).unwrap() as u8;
+    }
+    let slice: Vec<u8> = ep[4..8].iter().map(|p| p - 4).collect();
+    (
+        get_index_of_permutation(&ud_perm) as u16,
+        get_index_of_permutation(&slice) as u16,
+    )
+}
+
+/// Builds the move table of an edge coordinate of the second phase, as
+/// `coord` does for the moves of `ALL_MOVES`.
+fn build_phase_2_table(
+    size: usize,
+    to_ep: &dyn Fn(u16) -> [u8; 12],
+    from_ep: &dyn Fn(&[u8; 12]) -> u16,
+) -> Vec<[u16; 10]> {
+    (0..size)
+        .map(|i| {
+            let state = CubeState {
+                ep: to_ep(i as u16),
+                ..CubeState::default()
+            };
+            let mut row = [0; 10];
+            for (entry, &m) in row.iter_mut().zip(PHASE_2_MOVES.iter()) {
+                *entry = from_ep(&state.apply_move_instance(&ALL_MOVES[m]).ep);
+            }
+            row
+        })
+        .collect()
+}
+
+impl TwoPhaseTables {
+    fn new() -> Self {
+        let slice_of_locations = &MOVE_TABLES.edge_sets;
+        let mut locations_of_slice = vec![0; N_SLICE];
+        for (i, &slice) in slice_of_locations.iter().enumerate() {
+            locations_of_slice[slice as usize] = i;
+        }
+        let slice: Vec<[u16; 18]> = locations_of_slice
+            .iter()
+            .map(|&i| {
+                let mut row = [0; 18];
+                for (m, entry) in row.iter_mut().enumerate() {
+                    *entry = slice_of_locations[MOVE_TABLES.edge_locations[i][m] as usize];
+                }
+                row
+            })
+            .collect();
+        let solved_slice = slice_of_locations[CoordState::solved().edges[1] as usize];
+
+        let ud_edges = build_phase_2_table(N_UD_EDGES, &|i| get_edges_of_indices(i, 0), &|ep| {
+            get_indices_of_edges(ep).0
+        });
+        let slice_perm =
+            build_phase_2_table(N_SLICE_PERM, &|i| get_edges_of_indices(0, i), &|ep| {
+                get_indices_of_edges(ep).1
+            });
+
+        let twist_slice_pruning =
+            fill_pruning_table(N_TWIST * N_SLICE, solved_slice as usize, 18, &|index, m| {
+                let (twist, s) = (index / N_SLICE, index % N_SLICE);
+                MOVE_TABLES.twist[twist][m] as usize * N_SLICE + slice[s][m] as usize
+            });
+        let flip_slice_pruning =
+            fill_pruning_table(N_FLIP * N_SLICE, solved_slice as usize, 18, &|index, m| {
+                let (flip, s) = (index / N_SLICE, index % N_SLICE);
+                MOVE_TABLES.flip[flip][m] as usize * N_SLICE + slice[s][m] as usize
+            });
+        let corners_slice_pruning =
+            fill_pruning_table(N_CORNER_PERM * N_SLICE_PERM, 0, 10, &|index, m| {
+                let (corners, sp) = (index / N_SLICE_PERM, index % N_SLICE_PERM);
+                MOVE_TABLES.corner_perm[corners][PHASE_2_MOVES[m]] as usize * N_SLICE_PERM
+                    + slice_perm[sp][m] as usize
+            });
+        let edges_slice_pruning =
+            fill_pruning_table(N_UD_EDGES * N_SLICE_PERM, 0, 10, &|index, m| {
+                let (edges, sp) = (index / N_SLICE_PERM, index % N_SLICE_PERM);
+                ud_edges[edges][m] as usize * N_SLICE_PERM + slice_perm[sp][m] as usize
+            });
+
+        TwoPhaseTables {
+            slice,
+            ud_edges,
+            slice_perm,
+            twist_slice_pruning,
+            flip_slice_pruning,
+            corners_slice_pruning,
+            edges_slice_pruning,
+        }
+    }
+
+    fn get_phase_1_h_value(&self, node: &Phase1Coord) -> u8 {
+        let slice = node.slice as usize;
+        std::cmp::max(
+            self.twist_slice_pruning[node.twist as usize * N_SLICE + slice],
+            self.flip_slice_pruning[node.flip as usize * N_SLICE + slice],
+        )
+    }
+
+    fn get_phase_2_h_value(&self, node: &Phase2Coord) -> u8 {
+        let slice_perm = node.slice_perm as usize;
+        std::cmp::max(
+            self.corners_slice_pruning[node.corner_perm as usize * N_SLICE_PERM + slice_perm],
+            self.edges_slice_pruning[node.ud_edges as usize * N_SLICE_PERM + slice_perm],
+        )
+    }
+}
+
+/// The coordinates of the first phase: the orientations of the pieces and
+/// the set of positions of the E slice edges.
+#[derive(Clone, Copy)]
+struct Phase1Coord {
+    twist: u16,
+    flip: u16,
+    slice: u16,
+}
+
+/// The coordinates of the second phase: the permutations of the corners,
+/// of the edges outside the E slice and of the edges inside it.
+#[derive(Clone, Copy)]
+struct Phase2Coord {
+    corner_perm: u16,
+    ud_edges: u16,
+    slice_perm: u16,
+}
+
+/// A solver implementing Kociemba's two-phase algorithm.
+///
+/// The state must be solvable, as the search would otherwise go on
+/// for a very long time.
+pub struct TwoPhaseSolver {
+    start_state: CubeState,
+}
+
+impl TwoPhaseSolver {
+    pub fn new(state: impl Into<CubeState>) -> Self {
+        TwoPhaseSolver {
+            start_state: state.into(),
+        }
+    }
+
+    /// Searches for the first phase with exactly `depth` moves left, then
+    /// for the second phase from each state of the subgroup reached.
+    fn search_phase_1(
+        start: &CubeState,
+        node: Phase1Coord,
+        depth: u8,
+        allowed_moves: u8,
+        path: &mut Vec<MoveInstance>,
+    ) -> bool {
+        let tables = &*TABLES;
+        if tables.get_phase_1_h_value(&node) > depth {
+            return false;
+        }
+        if depth == 0 {
+            // the pruning tables are zero only within the subgroup
+            return Self::solve_phase_2(start, allowed_moves, path);
+        }
+        for (i, m) in ALL_MOVES
+            .iter()
+            .enumerate()
+            .filter(|(_, mo)| (1 << get_basemove_pos(mo.basemove)) & allowed_moves == 0)
+        {
+            let next = Phase1Coord {
+                twist: MOVE_TABLES.twist[node.twist as usize][i],
+                flip: MOVE_TABLES.flip[node.flip as usize][i],
+                slice: tables.slice[node.slice as usize][i],
+            };
+            path.push(*m);
+            let next_allowed = get_allowed_post_moves(allowed_moves, Some(m.basemove));
+            if Self::search_phase_1(start, next, depth - 1, next_allowed, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    /// Solves the second phase optimally from the end of the first phase.
+    fn solve_phase_2(start: &CubeState, allowed_moves: u8, path: &mut Vec<MoveInstance>) -> bool {
+        let state = start.apply_move_instances(&MoveSequence(path.clone()));
+        let (ud_edges, slice_perm) = get_indices_of_edges(&state.ep);
+        let node = Phase2Coord {
+            corner_perm: get_index_of_permutation(&state.cp) as u16,
+            ud_edges,
+            slice_perm,
+        };
+        let h = TABLES.get_phase_2_h_value(&node);
+        (h..=MAX_PHASE_2_LENGTH).any(|depth| search_phase_2(node, depth, allowed_moves, path))
+    }
+}
+
+/// Searches for the second phase with exactly `depth` moves left.
+fn search_phase_2(
+    node: Phase2Coord,
+    depth: u8,
+    allowed_moves: u8,
+    path: &mut Vec<MoveInstance>,
+) -> bool {
+    let tables = &*TABLES;
+    let h = tables.get_phase_2_h_value(&node);
+    if h > depth {
+        return false;
+    }
+    if depth == 0 {
+        return true;
+    }
+    for (i, &m) in PHASE_2_MOVES.iter().enumerate() {
+        let mov = ALL_MOVES[m];
+        if (1 << get_basemove_pos(mov.basemove)) & allowed_moves != 0 {
+            continue;
+        }
+        let next = Phase2Coord {
+            corner_perm: MOVE_TABLES.corner_perm[node.corner_perm as usize][m],
+            ud_edges: tables.ud_edges[node.ud_edges as usize][i],
+            slice_perm: tables.slice_perm[node.slice_perm as usize][i],
+        };
+        path.push(mov);
+        let next_allowed = get_allowed_post_moves(allowed_moves, Some(mov.basemove));
+        if search_phase_2(next, depth - 1, next_allowed, path) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+impl Solver for TwoPhaseSolver {
+    fn get_start_state(&self) -> &CubeState {
+        &self.start_state
+    }
+
+    fn solve(&self) -> MoveSequence {
+        let tables = &*TABLES;
+        let (rotation, start) = center_state(&self.start_state);
+        let coord = CoordState::from_state(&start);
+        let node = Phase1Coord {
+            twist: coord.twist,
+            flip: coord.flip,
+            slice: MOVE_TABLES.edge_sets[coord.edges[1] as usize],
+        };
+        let mut path = vec![];
+        let h = tables.get_phase_1_h_value(&node);
+        for depth in h..=MAX_PHASE_1_LENGTH {
+            if Self::search_phase_1(&start, node, depth, 0, &mut path) {
+                break;
+            }
+        }
+        MoveSequence([rotation, path].concat()).simplify().0
+    }
+}
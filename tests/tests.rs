@@ -1,9 +1,27 @@
 #[cfg(test)]
 mod tests {
+    use rusty_rubik::algdb::*;
+    use rusty_rubik::algorithm::*;
+    use rusty_rubik::bld::*;
+    use rusty_rubik::coord::*;
     use rusty_rubik::cube::*;
+    use rusty_rubik::cube_move;
+    use rusty_rubik::cycle::*;
+    use rusty_rubik::facelet::*;
+    use rusty_rubik::lastlayer::*;
+    use rusty_rubik::masked::*;
+    use rusty_rubik::metric::*;
+    use rusty_rubik::packed::*;
     use rusty_rubik::parser::*;
     use rusty_rubik::pruning::*;
+    use rusty_rubik::reconstruction::*;
+    use rusty_rubik::render::*;
+    use rusty_rubik::scrambler::*;
     use rusty_rubik::solver::*;
+    use rusty_rubik::steps::*;
+    use rusty_rubik::svg::*;
+    use rusty_rubik::symmetry::*;
+    use rusty_rubik::twophase::*;
     // PARSER TESTS
     #[test]
     fn parse_single_move() {
@@ -45,8 +63,71 @@ mod tests {
         )
     }
 
+    #[test]
+    fn parse_extended_moves() {
+        let parsed = parse_scramble("Rw r' M2 E S' x y2 z' Fw2 d").unwrap();
+        let expected = vec![
+            MoveInstance::new(BaseMoveToken::Rw, Direction::Normal),
+            MoveInstance::new(BaseMoveToken::Rw, Direction::Prime),
+            MoveInstance::new(BaseMoveToken::M, Direction::Double),
+            MoveInstance::new(BaseMoveToken::E, Direction::Normal),
+            MoveInstance::new(BaseMoveToken::S, Direction::Prime),
+            MoveInstance::new(BaseMoveToken::X, Direction::Normal),
+            MoveInstance::new(BaseMoveToken::Y, Direction::Double),
+            MoveInstance::new(BaseMoveToken::Z, Direction::Prime),
+            MoveInstance::new(BaseMoveToken::Fw, Direction::Double),
+            MoveInstance::new(BaseMoveToken::Dw, Direction::Normal),
+        ];
+        assert_eq!(parsed, expected);
+        assert!(parse_scramble("X").is_err());
+        assert!(parse_scramble("Mw").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_trailing_characters() {
+        for token in ["R'2", "R2'", "R''", "R22", "U'x", "Rw2w"].iter() {
+            assert!(parse_scramble(token).is_err(), "{}", token);
+            assert!(parse_scramble(&format!("F {} U", token)).is_err());
+        }
+    }
+
+    #[test]
+    fn display_extended_moves() {
+        let seq = MoveSequence(parse_scramble("r U R' M2 x' y2 z").unwrap());
+        assert_eq!(seq.to_string(), "Rw U R' M2 x' y2 z");
+        assert_eq!(seq.invert().to_string(), "z' y2 x M2 R U' Rw'");
+    }
+
     // CUBE STRUCTURE TESTS
 
+    #[test]
+    fn simplify_merges_and_cancels() {
+        let cases = [
+            ("R R", "R2", 1),
+            ("R R'", "", 2),
+            ("R L R", "L R2", 1),
+            ("R U U' R'", "", 4),
+            ("D U F2 F2 L", "U D L", 2),
+            ("R2 R' U M M' x x'", "R U", 5),
+            ("R U R' U'", "R U R' U'", 0),
+        ];
+        for (seq, simplified, cancelled) in cases.iter() {
+            let (result, count) = MoveSequence(parse_scramble(seq).unwrap()).simplify();
+            assert_eq!(result.to_string(), *simplified);
+            assert_eq!(count, *cancelled);
+        }
+    }
+
+    #[test]
+    fn simplify_preserves_state() {
+        let seq = MoveSequence(parse_scramble("R L R' U2 U D' Rw x' M' F B F' S z").unwrap());
+        let (simplified, _) = seq.simplify();
+        assert_eq!(
+            CubeState::default().apply_move_instances(&seq),
+            CubeState::default().apply_move_instances(&simplified)
+        );
+    }
+
     #[test]
     fn create_new_move_instance() {
         let move_instance = MoveInstance::new(BaseMoveToken::F, Direction::Prime);
@@ -62,7 +143,1682 @@ mod tests {
         assert_eq!(ep, 0);
     }
 
+    #[test]
+    fn state_of_index_round_trips() {
+        assert_eq!(get_state_of_index(0, 0, 0), CubeState::default());
+        for seed in 0..100 {
+            let state = CubeState::random_with_seed(seed);
+            let (c, eo, ep) = get_index_of_state(&state);
+            assert_eq!(get_state_of_index(c, eo, ep), state);
+        }
+        for &index in [0, 1, 2186, 2187, 88179839].iter() {
+            let (cp, co) = get_corners_of_index(index);
+            let state = CubeState {
+                cp,
+                co,
+                ..CubeState::default()
+            };
+            assert_eq!(get_index_of_state(&state).0, index);
+        }
+        for index in 0..2048 {
+            let state = CubeState {
+                eo: get_eo_of_index(index),
+                ..CubeState::default()
+            };
+            assert_eq!(get_index_of_state(&state).1, index);
+        }
+        let state = CubeState {
+            ep: get_ep_of_index(479001599),
+            ..CubeState::default()
+        };
+        assert_eq!(state.ep, [11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn random_state_is_reproducible() {
+        assert_eq!(
+            CubeState::random_with_seed(42),
+            CubeState::random_with_seed(42)
+        );
+        assert_ne!(
+            CubeState::random_with_seed(42),
+            CubeState::random_with_seed(43)
+        );
+    }
+
+    #[test]
+    fn random_state_has_valid_orientations() {
+        for seed in 0..100 {
+            let state = CubeState::random_with_seed(seed);
+            let co_sum: i32 = state.co.iter().map(|&x| x as i32).sum();
+            let eo_sum: i32 = state.eo.iter().map(|&x| x as i32).sum();
+            assert_eq!(co_sum.rem_euclid(3), 0);
+            assert_eq!(eo_sum % 2, 0);
+            assert!(state.co.iter().all(|&x| x == 0 || x == 1 || x == -1));
+            assert!(state.eo.iter().all(|&x| x == 0 || x == 1));
+        }
+    }
+
+    #[test]
+    fn random_state_has_matching_parity() {
+        fn parity(perm: &[u8]) -> usize {
+            let mut inversions = 0;
+            for i in 0..perm.len() {
+                for j in (i + 1)..perm.len() {
+                    if perm[i] > perm[j] {
+                        inversions += 1;
+                    }
+                }
+            }
+            inversions % 2
+        }
+        for seed in 0..100 {
+            let state = CubeState::random_with_seed(seed);
+            let mut cp = state.cp.to_vec();
+            let mut ep = state.ep.to_vec();
+            assert_eq!(parity(&cp), parity(&ep));
+            cp.sort_unstable();
+            ep.sort_unstable();
+            assert_eq!(cp, (0..8).collect::<Vec<u8>>());
+            assert_eq!(ep, (0..12).collect::<Vec<u8>>());
+        }
+    }
+
+    #[test]
+    fn solved_and_scrambled_states_are_valid() {
+        assert_eq!(CubeState::default().validate(), Ok(()));
+        let scramble = MoveSequence(parse_scramble("R U2 F' L D B2 R'").unwrap());
+        let state = CubeState::default().apply_move_instances(&scramble);
+        assert!(state.is_solvable());
+        for seed in 0..20 {
+            assert!(CubeState::random_with_seed(seed).is_solvable());
+        }
+    }
+
+    #[test]
+    fn validate_detects_broken_rules() {
+        let mut state = CubeState::default();
+        state.cp[0] = 1;
+        assert_eq!(
+            state.validate(),
+            Err(InvalidStateError::InvalidCornerPermutation)
+        );
+
+        let mut state = CubeState::default();
+        state.ep[3] = 12;
+        assert_eq!(
+            state.validate(),
+            Err(InvalidStateError::InvalidEdgePermutation)
+        );
+
+        let mut state = CubeState::default();
+        state.co[2] = 2;
+        assert_eq!(
+            state.validate(),
+            Err(InvalidStateError::InvalidCornerOrientation(2))
+        );
+
+        let mut state = CubeState::default();
+        state.eo[5] = -1;
+        assert_eq!(
+            state.validate(),
+            Err(InvalidStateError::InvalidEdgeOrientation(5))
+        );
+
+        let mut state = CubeState::default();
+        state.co[0] = 1;
+        assert_eq!(state.validate(), Err(InvalidStateError::TwistedCorner));
+
+        let mut state = CubeState::default();
+        state.eo[0] = 1;
+        assert_eq!(state.validate(), Err(InvalidStateError::FlippedEdge));
+
+        let mut state = CubeState::default();
+        state.ep.swap(0, 1);
+        assert_eq!(state.validate(), Err(InvalidStateError::ParitySwap));
+    }
+
+    #[test]
+    fn extended_moves_decompose_into_face_and_slice_moves() {
+        let cases = [
+            ("Rw", "R M'"),
+            ("Lw", "L M"),
+            ("Uw", "U E'"),
+            ("Dw", "D E"),
+            ("Fw", "F S"),
+            ("Bw", "B S'"),
+            ("x", "R M' L'"),
+            ("y", "U E' D'"),
+            ("z", "F S B'"),
+        ];
+        for (mov, decomposition) in cases.iter() {
+            let lhs = MoveSequence(parse_scramble(mov).unwrap());
+            let rhs = MoveSequence(parse_scramble(decomposition).unwrap());
+            assert_eq!(
+                CubeState::default().apply_move_instances(&lhs),
+                CubeState::default().apply_move_instances(&rhs)
+            );
+        }
+    }
+
+    #[test]
+    fn extended_moves_invert() {
+        let seq = MoveSequence(parse_scramble("r U R' M2 E S' x y z' Bw2 l").unwrap());
+        let state = CubeState::default().apply_move_instances(&seq);
+        assert!(state.is_solvable());
+        assert_ne!(state, CubeState::default());
+        assert_eq!(
+            state.apply_move_instances(&seq.invert()),
+            CubeState::default()
+        );
+    }
+
+    #[test]
+    fn rotations_move_centers() {
+        let seq = MoveSequence(parse_scramble("x").unwrap());
+        let state = CubeState::default().apply_move_instances(&seq);
+        // the F center is now on top, and the U center is now at the back
+        assert_eq!(state.centers, [4, 5, 2, 3, 1, 0]);
+        let bad = CubeState {
+            centers: [1, 0, 2, 3, 4, 5],
+            ..CubeState::default()
+        };
+        assert_eq!(bad.validate(), Err(InvalidStateError::InvalidCenters));
+    }
+
+    fn state_of(moves: &str) -> CubeState {
+        CubeState::default().apply_move_instances(&MoveSequence(parse_scramble(moves).unwrap()))
+    }
+
+    #[test]
+    fn compose_matches_concatenation() {
+        let a = "R U2 F' Rw D x";
+        let b = "M' L B2 y' S U'";
+        assert_eq!(
+            state_of(a).compose(&state_of(b)),
+            state_of(&format!("{} {}", a, b))
+        );
+        let state = state_of(a);
+        assert_eq!(state.compose(&CubeState::default()), state);
+        assert_eq!(CubeState::default().compose(&state), state);
+    }
+
+    #[test]
+    fn inverse_undoes_state() {
+        let seq = MoveSequence(parse_scramble("R U2 F' Lw D' M E2 z").unwrap());
+        let state = CubeState::default().apply_move_instances(&seq);
+        assert_eq!(
+            state.inverse(),
+            CubeState::default().apply_move_instances(&seq.invert())
+        );
+        assert!(state.compose(&state.inverse()).is_identity());
+        assert!(state.inverse().compose(&state).is_identity());
+        assert!(!state.is_identity());
+    }
+
+    #[test]
+    fn order_of_states() {
+        let cases = [
+            ("", 1),
+            ("R", 4),
+            ("R2", 2),
+            ("R U", 105),
+            ("R U R' U'", 6),
+            ("R U2 D' B D'", 1260),
+            ("M2 U M2 U2 M2 U M2", 2),
+            ("x", 4),
+        ];
+        for (moves, order) in cases.iter() {
+            let state = state_of(moves);
+            assert_eq!(state.order(), *order);
+            let repeated = (0..*order).fold(CubeState::default(), |acc, _| acc.compose(&state));
+            assert!(repeated.is_identity());
+        }
+    }
+
+    #[test]
+    fn cycle_notation_of_states() {
+        let cases = [
+            ("", ""),
+            ("U", "(UBL UBR UFR UFL)(UB UR UF UL)"),
+            ("U2", "(UBL UFR)(UBR UFL)(UB UF)(UR UL)"),
+            ("R", "(UBR BRD DFR FRU)(UR BR DR FR)"),
+            ("R U R' U R U2 R'", "(UBL RUF)-(UBR FLU)+(UB UL UR)"),
+            ("R U R' U' R' F R2 U' R' U' R U R' F'", "(UBR UFR)(UR UL)"),
+            ("M' U M U2 M' U M", "(UB RU LU)"),
+        ];
+        for (moves, notation) in cases.iter() {
+            let state = state_of(moves);
+            assert_eq!(state.to_cycle_notation(), *notation);
+            assert_eq!(CubeState::from_cycle_notation(notation), Ok(state));
+        }
+    }
+
+    #[test]
+    fn cycle_decomposition_gives_twists() {
+        let state = state_of("R' D' R D R' D' R D U D' R' D R D' R' D R U'");
+        let cycles = state.get_cycles();
+        assert_eq!(
+            cycles,
+            vec![
+                PieceCycle {
+                    piece_type: PieceType::Corner,
+                    stickers: vec![(1, 0)],
+                    twist: 1,
+                },
+                PieceCycle {
+                    piece_type: PieceType::Corner,
+                    stickers: vec![(2, 0)],
+                    twist: 2,
+                },
+            ]
+        );
+        assert_eq!(state.to_cycle_notation(), "(UBR)+(UFR)-");
+    }
+
+    #[test]
+    fn cycle_notation_round_trips() {
+        for seed in 0..50 {
+            let state = CubeState::random_with_seed(seed);
+            let notation = state.to_cycle_notation();
+            assert_eq!(CubeState::from_cycle_notation(&notation), Ok(state));
+        }
+    }
+
+    #[test]
+    fn parse_cycle_notation_variants() {
+        let twisted = CubeState::from_cycle_notation("(UFR)+ (UBL)-").unwrap();
+        assert!(twisted.is_solvable());
+        assert_eq!(twisted.co[2], 1);
+        assert_eq!(twisted.co[0], -1);
+        assert_eq!(
+            CubeState::from_cycle_notation("(URF, UBR, LUB)"),
+            CubeState::from_cycle_notation("(UFR UBR LUB)")
+        );
+        assert_eq!(
+            CubeState::from_cycle_notation("(UFR UF)"),
+            Err(CycleNotationError::MixedPieceTypes)
+        );
+        assert_eq!(
+            CubeState::from_cycle_notation("(UF UR)(UF UL)"),
+            Err(CycleNotationError::RepeatedPiece(String::from("UF")))
+        );
+        assert_eq!(
+            CubeState::from_cycle_notation("(UFX)"),
+            Err(CycleNotationError::UnknownPiece(String::from("UFX")))
+        );
+        assert_eq!(
+            CubeState::from_cycle_notation("(UF UR"),
+            Err(CycleNotationError::UnbalancedParentheses)
+        );
+        assert_eq!(
+            CubeState::from_cycle_notation("(UF)-"),
+            Err(CycleNotationError::InvalidTwist)
+        );
+        assert_eq!(
+            CubeState::from_cycle_notation("()"),
+            Err(CycleNotationError::EmptyCycle)
+        );
+    }
+
+    #[test]
+    fn bld_memo_traces_buffer_cycle() {
+        let memo = get_memo(&state_of("U"), &BldConfig::default());
+        assert_eq!(memo.corners, vec!['B', 'A', 'D']);
+        assert_eq!(memo.edges, vec!['B', 'A', 'D']);
+        assert!(memo.parity);
+        assert_eq!(memo.to_string(), "Edges: BA D\nCorners: BA D\nParity: yes");
+    }
+
+    #[test]
+    fn bld_memo_breaks_cycles() {
+        let memo = get_memo(
+            &state_of("R U R' U' R' F R2 U' R' U' R U R' F'"),
+            &BldConfig::default(),
+        );
+        assert_eq!(memo.get_corner_pairs(), vec!["B"]);
+        assert_eq!(memo.get_edge_pairs(), vec!["BD", "B"]);
+        assert!(memo.parity);
+    }
+
+    #[test]
+    fn bld_memo_lists_twists_and_flips() {
+        let memo = get_memo(
+            &state_of("R' D' R D R' D' R D U D' R' D R D' R' D R U'"),
+            &BldConfig::default(),
+        );
+        assert!(memo.corners.is_empty());
+        assert!(memo.edges.is_empty());
+        assert_eq!(memo.twisted_corners, vec!['Q']);
+        assert!(!memo.parity);
+
+        let flipped = CubeState::from_cycle_notation("(UR)+(UF)+").unwrap();
+        let memo = get_memo(&flipped, &BldConfig::default());
+        assert!(memo.edges.is_empty());
+        assert_eq!(memo.flipped_edges, vec!['M']);
+    }
+
+    #[test]
+    fn bld_memo_with_custom_buffers_and_scheme() {
+        let scheme = LetterScheme::new(
+            "abcd efgh ijkl mnop qrst uvwx",
+            "abcd efgh ijkl mnop qrst uvwx",
+        )
+        .unwrap();
+        let config = BldConfig::new(scheme, "UBL", "DF").unwrap();
+        let memo = get_memo(&state_of("U"), &config);
+        assert_eq!(memo.corners, vec!['d', 'c', 'b']);
+        assert_eq!(memo.edges, vec!['a', 'd', 'c', 'b', 'a']);
+        assert!(LetterScheme::new("abc", "def").is_none());
+        assert_eq!(
+            BldConfig::new(LetterScheme::default(), "UF", "DF"),
+            Err(CycleNotationError::UnknownPiece(String::from("UF")))
+        );
+    }
+
+    #[test]
+    fn move_counts_in_metrics() {
+        let cases = [
+            ("R U2 R'", [3, 4, 3, 3, 3]),
+            ("M2 U M2 U2 M2 U M2", [11, 20, 7, 7, 7]),
+            ("x R U R' x'", [3, 3, 3, 5, 3]),
+            ("Rw U2 Rw'", [3, 4, 3, 3, 3]),
+            ("R L' U2 D E'", [6, 7, 5, 5, 2]),
+            ("R x L", [2, 2, 2, 3, 2]),
+            ("", [0, 0, 0, 0, 0]),
+        ];
+        let metrics = [
+            Metric::HTM,
+            Metric::QTM,
+            Metric::STM,
+            Metric::ETM,
+            Metric::ATM,
+        ];
+        for (moves, counts) in cases.iter() {
+            let seq = MoveSequence(parse_scramble(moves).unwrap());
+            for (metric, count) in metrics.iter().zip(counts.iter()) {
+                assert_eq!(
+                    seq.get_move_count(*metric),
+                    *count,
+                    "{} in {:?}",
+                    moves,
+                    metric
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn convert_sequences_between_metrics() {
+        let seq = MoveSequence(parse_scramble("R2 U' M E2 S' Rw Lw2 Uw' Dw Fw2 Bw' x2").unwrap());
+        for metric in [
+            Metric::HTM,
+            Metric::QTM,
+            Metric::STM,
+            Metric::ETM,
+            Metric::ATM,
+        ]
+        .iter()
+        {
+            let converted = seq.convert_to_metric(*metric);
+            assert!(converted
+                .get_moves()
+                .iter()
+                .all(|m| metric.is_metric_move(m)));
+            assert_eq!(
+                CubeState::default().apply_move_instances(&converted),
+                CubeState::default().apply_move_instances(&seq)
+            );
+        }
+        let qtm = MoveSequence(parse_scramble("R2 M' y2").unwrap()).convert_to_metric(Metric::QTM);
+        assert_eq!(qtm.to_string(), "R R L R' x y2");
+        assert_eq!(qtm.get_move_count(Metric::QTM), 4);
+        let htm = MoveSequence(parse_scramble("r U r'").unwrap()).convert_to_metric(Metric::HTM);
+        assert_eq!(htm.to_string(), "L x U L' x'");
+        assert_eq!(seq.convert_to_metric(Metric::STM), seq);
+    }
+
+    #[test]
+    fn cube3_moves_match_cube_state() {
+        use rusty_rubik::puzzle::*;
+        let moves = [(3, 1), (0, 1), (4, 3), (1, 2)];
+        let cube = moves
+            .iter()
+            .fold(Cube3::default(), |cube, &(axis, rotation)| {
+                Puzzle::make_move(
+                    &cube,
+                    CubeMove {
+                        axis,
+                        depth: 1,
+                        rotation,
+                    },
+                )
+                .unwrap()
+            });
+        let state = state_of("R U F' D2");
+        assert_eq!(CubeState::from(&cube), state);
+        assert_eq!(CubeState::from(Cube3::from(&state)), state);
+        assert_eq!(Cube3::get_all_moves().len(), 18);
+
+        let wide = CubeMove {
+            axis: 3,
+            depth: 2,
+            rotation: 1,
+        };
+        assert!(Puzzle::make_move(&cube, wide.clone()).is_none());
+        let mut moved = cube.clone();
+        assert!(moved.make_move_mut(wide).is_none());
+        assert_eq!(CubeState::from(&moved), state);
+    }
+
+    #[test]
+    fn cube_move_conversions() {
+        use rusty_rubik::puzzle::*;
+        for m in parse_scramble("R U' F2 Rw Dw'").unwrap() {
+            let cube_move = CubeMove::from_move_instance(&m).unwrap();
+            assert_eq!(cube_move.to_move_instance(), Some(m));
+        }
+        let slice = MoveInstance::new(BaseMoveToken::M, Direction::Normal);
+        assert_eq!(CubeMove::from_move_instance(&slice), None);
+        assert!(Cube3::default()
+            .apply_move_instances(&MoveSequence(vec![slice]))
+            .is_none());
+    }
+
+    // ALGORITHM TESTS
+
+    fn alg(moves: &str) -> Algorithm {
+        Algorithm::Sequence(MoveSequence(parse_scramble(moves).unwrap()))
+    }
+
+    #[test]
+    fn expand_nested_algorithms() {
+        let comm = Algorithm::Commutator(Box::new(alg("R D R'")), Box::new(alg("U2")));
+        let conj = Algorithm::Conjugate(Box::new(alg("R")), Box::new(comm));
+        assert_eq!(conj.to_string(), "[R: [R D R', U2]]");
+        assert_eq!(conj.expand().to_string(), "R R D R' U2 R D' R' U2 R'");
+
+        let sexy = Algorithm::Repeat(Box::new(alg("R U R' U'")), 6);
+        assert_eq!(sexy.to_string(), "(R U R' U')6");
+        assert_eq!(sexy.apply(&CubeState::default()), CubeState::default());
+
+        let nested = Algorithm::Conjugate(
+            Box::new(alg("U")),
+            Box::new(Algorithm::Commutator(
+                Box::new(Algorithm::Conjugate(Box::new(alg("R")), Box::new(alg("U")))),
+                Box::new(alg("D")),
+            )),
+        );
+        assert_eq!(nested.to_string(), "[U: [[R: U], D]]");
+        assert_eq!(nested.expand().to_string(), "U R U R' D R U' R' D' U'");
+    }
+
+    #[test]
+    fn invert_algorithms() {
+        let algs = [
+            Algorithm::Commutator(Box::new(alg("R U' R'")), Box::new(alg("D"))),
+            Algorithm::Conjugate(
+                Box::new(alg("R")),
+                Box::new(Algorithm::Commutator(
+                    Box::new(alg("R D R'")),
+                    Box::new(alg("U2")),
+                )),
+            ),
+            Algorithm::Repeat(Box::new(alg("R U")), 3),
+            Algorithm::Concat(vec![
+                alg("F"),
+                Algorithm::Commutator(Box::new(alg("R")), Box::new(alg("U"))),
+            ]),
+        ];
+        for a in algs.iter() {
+            assert_eq!(a.invert().expand(), a.expand().invert());
+        }
+        let comm = Algorithm::Commutator(Box::new(alg("R U' R'")), Box::new(alg("D")));
+        assert_eq!(comm.invert().to_string(), "[D, R U' R']");
+    }
+
+    #[test]
+    fn convert_commutator_and_conjugate() {
+        let comm = Commutator(
+            MoveSequence(parse_scramble("R U' R'").unwrap()),
+            MoveSequence(parse_scramble("D").unwrap()),
+        );
+        assert_eq!(comm.to_string(), "[R U' R', D]");
+        assert_eq!(comm.expand(), Algorithm::from(comm.clone()).expand());
+        let conj = Conjugate(MoveSequence(parse_scramble("F").unwrap()), comm);
+        assert_eq!(conj.to_string(), "[F: [R U' R', D]]");
+        assert_eq!(conj.expand().to_string(), "F R U' R' D R U R' D' F'");
+    }
+
+    #[test]
+    fn find_plain_commutator() {
+        let seq = MoveSequence(parse_scramble("R U' R' D R U R' D'").unwrap());
+        let found = find_commutator(&seq, 5).unwrap();
+        assert_eq!(found.to_string(), "[R U' R', D]");
+    }
+
+    #[test]
+    fn find_conjugated_commutator() {
+        let seq = MoveSequence(parse_scramble("F R U R' D R U' R' D' F'").unwrap());
+        let found = find_commutator(&seq, 5).unwrap();
+        assert_eq!(found.to_string(), "[F: [R U R', D]]");
+
+        // the setup move may also be absorbed into a shorter plain commutator
+        let seq = MoveSequence(parse_scramble("U R D R' U2 R D' R' U").unwrap());
+        let found = find_commutator(&seq, 5).unwrap();
+        assert_eq!(found.expand().simplify().0, seq);
+    }
+
+    #[test]
+    fn find_commutator_with_cancellation() {
+        // [R: [R D R', U2]] expands to R R D R' U2 R D' R' U2 R', i.e. R2 D ...
+        let seq = MoveSequence(parse_scramble("R2 D R' U2 R D' R' U2 R'").unwrap());
+        let found = find_commutator(&seq, 5).unwrap();
+        assert_eq!(found.expand().simplify().0, seq);
+        assert!(found.expand().get_moves().len() <= 10);
+    }
+
+    #[test]
+    fn find_no_commutator() {
+        let seq = MoveSequence(parse_scramble("R U").unwrap());
+        assert_eq!(find_commutator(&seq, 5), None);
+        let seq = MoveSequence(parse_scramble("R U' R' D R U R' D'").unwrap());
+        assert_eq!(find_commutator(&seq, 2), None);
+    }
+
+    // FACELET TESTS
+
+    #[test]
+    fn facelets_of_solved_state() {
+        assert_eq!(
+            CubeState::default().to_facelet_string(&ColorScheme::default()),
+            "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB"
+        );
+    }
+
+    #[test]
+    fn facelets_of_single_moves() {
+        let scheme = ColorScheme::default();
+        let cases = [
+            (
+                "U",
+                "UUUUUUUUUBBBRRRRRRRRRFFFFFFDDDDDDDDDFFFLLLLLLLLLBBBBBB",
+            ),
+            (
+                "R",
+                "UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB",
+            ),
+            (
+                "F",
+                "UUUUUULLLURRURRURRFFFFFFFFFRRRDDDDDDLLDLLDLLDBBBBBBBBB",
+            ),
+        ];
+        for (scramble, facelets) in cases.iter() {
+            let seq = MoveSequence(parse_scramble(scramble).unwrap());
+            let state = CubeState::default().apply_move_instances(&seq);
+            assert_eq!(state.to_facelet_string(&scheme), *facelets);
+            assert_eq!(
+                CubeState::from_facelet_string(facelets, &scheme).unwrap(),
+                state
+            );
+        }
+    }
+
+    #[test]
+    fn facelets_of_extended_moves() {
+        let scheme = ColorScheme::default();
+        let cases = [
+            (
+                "r U R'",
+                "UUUFFUFFLBRRURRURRRRUFDFFDFDBRDBDDBDFDDLLLLLLBLLBUBBUB",
+            ),
+            (
+                "M2 E S'",
+                "UDURBRUDURDRFUFRDRFBFLLLFBFDUDLFLDUDLULBDBLULBFBRRRBFB",
+            ),
+            (
+                "x y z",
+                "DDDDDDDDDFFFFFFFFFRRRRRRRRRUUUUUUUUUBBBBBBBBBLLLLLLLLL",
+            ),
+        ];
+        for (scramble, facelets) in cases.iter() {
+            let seq = MoveSequence(parse_scramble(scramble).unwrap());
+            let state = CubeState::default().apply_move_instances(&seq);
+            assert_eq!(state.to_facelet_string(&scheme), *facelets);
+            assert_eq!(
+                CubeState::from_facelet_string(facelets, &scheme).unwrap(),
+                state
+            );
+        }
+    }
+
+    #[test]
+    fn facelets_round_trip() {
+        let scheme = ColorScheme::western();
+        for seed in 0..50 {
+            let state = CubeState::random_with_seed(seed);
+            let facelets = state.to_facelet_string(&scheme);
+            assert_eq!(
+                CubeState::from_facelet_string(&facelets, &scheme).unwrap(),
+                state
+            );
+            assert_eq!(ColorScheme::from_centers(&facelets).unwrap(), scheme);
+            assert_eq!(
+                CubeState::from_facelets(&state.to_facelets()).unwrap(),
+                state
+            );
+        }
+    }
+
+    #[test]
+    fn facelet_strings_may_separate_faces() {
+        let scheme = ColorScheme::western();
+        let state = state_of("R U F'");
+        let facelets = state.to_facelet_string(&scheme);
+        let faces: Vec<&str> = (0..6).map(|i| &facelets[9 * i..9 * i + 9]).collect();
+        let spaced = faces.join(" \n");
+        assert_eq!(
+            CubeState::from_facelet_string(&spaced, &scheme).unwrap(),
+            state
+        );
+        assert_eq!(ColorScheme::from_centers(&spaced).unwrap(), scheme);
+        assert_eq!(
+            ColorScheme::from_centers(&facelets[1..]),
+            Err(FaceletError::WrongLength(53))
+        );
+    }
+
+    #[test]
+    fn facelets_report_errors() {
+        let scheme = ColorScheme::default();
+        let solved = CubeState::default().to_facelet_string(&scheme);
+        assert_eq!(
+            CubeState::from_facelet_string(&solved[1..], &scheme),
+            Err(FaceletError::WrongLength(53))
+        );
+        assert_eq!(
+            CubeState::from_facelet_string(&solved.replacen('U', "X", 1), &scheme),
+            Err(FaceletError::UnknownColor('X'))
+        );
+        assert_eq!(
+            CubeState::from_facelet_string(&solved.replacen('U', "R", 1), &scheme),
+            Err(FaceletError::WrongStickerCount(Face::U, 8))
+        );
+        // swap two stickers of the UFR corner so that it reads U F R
+        let mut chars: Vec<char> = solved.chars().collect();
+        chars.swap(9, 20);
+        let bad_corner: String = chars.into_iter().collect();
+        assert_eq!(
+            CubeState::from_facelet_string(&bad_corner, &scheme),
+            Err(FaceletError::InvalidCorner(2))
+        );
+        // flip the UF edge in place
+        let mut chars: Vec<char> = solved.chars().collect();
+        chars.swap(7, 19);
+        let flipped: String = chars.into_iter().collect();
+        assert_eq!(
+            CubeState::from_facelet_string(&flipped, &scheme),
+            Err(FaceletError::InvalidState(InvalidStateError::FlippedEdge))
+        );
+    }
+
+    // RENDERING TESTS
+
+    #[test]
+    fn render_solved_net() {
+        let expected = [
+            "      U U U",
+            "      U U U",
+            "      U U U",
+            "L L L F F F R R R B B B",
+            "L L L F F F R R R B B B",
+            "L L L F F F R R R B B B",
+            "      D D D",
+            "      D D D",
+            "      D D D",
+        ]
+        .join("\n");
+        assert_eq!(CubeState::default().to_string(), expected);
+    }
+
+    #[test]
+    fn render_net_with_color_scheme() {
+        let state = CubeState::default().apply_move_instance(&cube_move!(F, Normal));
+        let rendered = render_net_ascii(&state, &ColorScheme::western());
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[2], "      O O O");
+        assert_eq!(lines[3], "O O Y G G G W R R B B B");
+        assert_eq!(lines[6], "      R R R");
+    }
+
+    #[test]
+    fn svg_net_has_all_stickers() {
+        let colors = SvgColorScheme::default();
+        let svg = render_net_svg(&CubeState::default(), &colors);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 54);
+        for face in ALL_FACES.iter() {
+            let fill = format!("fill=\"{}\"", colors.get_color(*face));
+            assert_eq!(svg.matches(&fill).count(), 9);
+        }
+    }
+
+    #[test]
+    fn svg_scramble_matches_state() {
+        let colors = SvgColorScheme::default();
+        let scramble = MoveSequence(parse_scramble("R U R' U'").unwrap());
+        let state = CubeState::default().apply_move_instances(&scramble);
+        assert_eq!(
+            render_scramble_svg(&scramble, &colors),
+            render_net_svg(&state, &colors)
+        );
+    }
+
+    #[test]
+    fn svg_last_layer_view() {
+        let mut colors = SvgColorScheme::default();
+        colors.0[0] = String::from("gray");
+        let svg = render_last_layer_svg(&CubeState::default(), &colors);
+        assert_eq!(svg.matches("<rect").count(), 21);
+        assert_eq!(svg.matches("fill=\"gray\"").count(), 9);
+        assert_eq!(svg.matches("fill=\"#ffff00\"").count(), 0);
+    }
+
+    // SYMMETRY TESTS
+
+    #[test]
+    fn symmetry_group_structure() {
+        let all = Symmetry::all();
+        assert_eq!(all.len(), 48);
+        assert_eq!(all[0], Symmetry::identity());
+        assert_eq!(Symmetry::rotations().len(), 24);
+        assert!(Symmetry::rotations().iter().all(|s| !s.is_reflection()));
+        assert!(all[24..].iter().all(|s| s.is_reflection()));
+        for s in all.iter() {
+            assert_eq!(s.compose(&s.invert()), Symmetry::identity());
+            for t in all.iter() {
+                assert!(all.contains(&s.compose(t)));
+            }
+        }
+    }
+
+    #[test]
+    fn symmetry_conjugation_commutes_with_moves() {
+        let seq = MoveSequence(parse_scramble("R U2 F' Lw M E' S2 x y' z2 D B' Uw").unwrap());
+        let state = CubeState::default().apply_move_instances(&seq);
+        for s in Symmetry::all().iter() {
+            let conj_seq = seq.conjugate_by(s);
+            assert_eq!(
+                state.conjugate_by(s),
+                CubeState::default().apply_move_instances(&conj_seq)
+            );
+            assert_eq!(state.conjugate_by(s).conjugate_by(&s.invert()), state);
+        }
+    }
+
+    #[test]
+    fn symmetries_conjugate_unsolvable_states() {
+        let twisted = CubeState {
+            co: [1, 0, 0, 0, 0, 0, 0, 0],
+            ..CubeState::default()
+        };
+        assert!(!twisted.is_solvable());
+        for s in Symmetry::all().iter() {
+            let conj = twisted.conjugate_by(s);
+            assert!(!conj.is_solvable());
+            assert_eq!(conj.conjugate_by(&s.invert()), twisted);
+        }
+        // one clockwise or counterclockwise twist at any of the 8 corners
+        assert_eq!(twisted.get_symmetry_class().len(), 16);
+    }
+
+    #[test]
+    fn mirror_algorithms() {
+        let sune = MoveSequence(parse_scramble("R U R' U R U2 R'").unwrap());
+        assert_eq!(
+            sune.conjugate_by(&Symmetry::mirror_lr()).to_string(),
+            "L' U' L U' L' U2 L"
+        );
+        let seq = MoveSequence(parse_scramble("M x E").unwrap());
+        assert_eq!(
+            seq.conjugate_by(&Symmetry::mirror_lr()).to_string(),
+            "M x E'"
+        );
+    }
+
+    #[test]
+    fn rotation_symmetries_match_rotation_moves() {
+        let seq = MoveSequence(parse_scramble("R U F'").unwrap());
+        let state = CubeState::default().apply_move_instances(&seq);
+        for rotation in parse_scramble("x y z x' y2 z'").unwrap() {
+            let sym = Symmetry::from_rotation(&rotation).unwrap();
+            // transforming by a rotation r is the same as performing r' A r
+            let conj = MoveSequence(vec![
+                rotation.invert(),
+                seq.0[0],
+                seq.0[1],
+                seq.0[2],
+                rotation,
+            ]);
+            assert_eq!(
+                state.conjugate_by(&sym),
+                CubeState::default().apply_move_instances(&conj)
+            );
+        }
+        assert_eq!(Symmetry::from_rotation(&cube_move!(R, Normal)), None);
+    }
+
+    #[test]
+    fn symmetry_classes() {
+        let solved = CubeState::default();
+        assert_eq!(solved.get_symmetry_class(), vec![solved.clone()]);
+        assert_eq!(solved.get_self_symmetries().len(), 48);
+
+        let superflip = CubeState {
+            eo: [1; 12],
+            ..CubeState::default()
+        };
+        assert_eq!(superflip.get_self_symmetries().len(), 48);
+
+        let seq = MoveSequence(parse_scramble("R U").unwrap());
+        let state = CubeState::default().apply_move_instances(&seq);
+        let class = state.get_symmetry_class();
+        assert_eq!(class.len(), 48);
+        let canonical = state.get_canonical_representative();
+        assert_eq!(canonical, class[0]);
+        for s in Symmetry::all().iter() {
+            assert_eq!(
+                state.conjugate_by(s).get_canonical_representative(),
+                canonical
+            );
+        }
+    }
+
+    // COORDINATE TESTS
+    #[test]
+    fn coord_state_round_trips() {
+        assert_eq!(CoordState::solved().to_state(), CubeState::default());
+        for seed in 0..50 {
+            let state = CubeState::random_with_seed(seed);
+            let coord = CoordState::from_state(&state);
+            assert_eq!(coord.to_state(), state);
+            assert_eq!(get_index_of_coord(&coord), get_index_of_state(&state));
+        }
+    }
+
+    #[test]
+    fn move_tables_match_moves() {
+        for seed in 0..20 {
+            let state = CubeState::random_with_seed(seed);
+            let coord = CoordState::from_state(&state);
+            for (i, m) in ALL_MOVES.iter().enumerate() {
+                assert_eq!(
+                    MOVE_TABLES.apply_move(&coord, i),
+                    CoordState::from_state(&state.apply_move_instance(m))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn move_tables_follow_sequence() {
+        let seq = MoveSequence(parse_scramble("R U2 F' L D B2 R' U' F2 D'").unwrap());
+        let coord = seq.get_moves().iter().fold(CoordState::solved(), |acc, m| {
+            let i = ALL_MOVES.iter().position(|n| n == m).unwrap();
+            MOVE_TABLES.apply_move(&acc, i)
+        });
+        let state = CubeState::default().apply_move_instances(&seq);
+        assert_eq!(coord.to_state(), state);
+    }
+
+    #[test]
+    fn packed_state_round_trips() {
+        assert_eq!(
+            CubeState::from(PackedState::default()),
+            CubeState::default()
+        );
+        for seed in 0..50 {
+            let state = CubeState::random_with_seed(seed);
+            assert_eq!(CubeState::from(PackedState::from(&state)), state);
+        }
+        let rotated = state_of("x y' M E2 S");
+        assert_eq!(CubeState::from(PackedState::from(&rotated)), rotated);
+        assert!(PackedState::from(&rotated).0 < 1 << 118);
+    }
+
+    #[test]
+    fn packed_state_applies_moves() {
+        let seq = MoveSequence(parse_scramble("R U2 F' Lw D x M' E2 S B' y").unwrap());
+        let packed = PackedState::default().apply_move_instances(&seq);
+        assert_eq!(
+            CubeState::from(packed),
+            CubeState::default().apply_move_instances(&seq)
+        );
+        assert_eq!(
+            packed.apply_move_instances(&seq.invert()),
+            PackedState::default()
+        );
+    }
+
+    #[test]
+    fn a_star_solves_short_scramble() {
+        let scramble = MoveSequence(parse_scramble("R U F'").unwrap());
+        let state = CubeState::default().apply_move_instances(&scramble);
+        let solution = AStarSolver::new(state.clone()).solve();
+        assert_eq!(solution.get_moves().len(), 3);
+        assert_eq!(state.apply_move_instances(&solution), CubeState::default());
+    }
+
+    #[test]
+    fn solvers_handle_moved_centers() {
+        for moves in ["M", "x", "R E' z"].iter() {
+            let state = state_of(moves);
+            assert_eq!(
+                state.apply_move_instances(&AStarSolver::new(state.clone()).solve()),
+                CubeState::default()
+            );
+            assert_eq!(
+                state.apply_move_instances(&TwoPhaseSolver::new(state.clone()).solve()),
+                CubeState::default()
+            );
+        }
+        let rotation = state_of("x").get_home_rotation().unwrap();
+        assert_eq!(rotation.get_moves().len(), 1);
+        assert_eq!(AStarSolver::new(state_of("x")).solve(), rotation);
+    }
+
+    #[test]
+    fn two_phase_solves_random_states() {
+        for seed in 0..5 {
+            let state = CubeState::random_with_seed(seed);
+            let solution = TwoPhaseSolver::new(state.clone()).solve();
+            assert!(solution.get_moves().len() <= 30);
+            assert_eq!(state.apply_move_instances(&solution), CubeState::default());
+        }
+    }
+
+    #[test]
+    fn scramble_reaches_state() {
+        let scramble = MoveSequence(parse_scramble("R U R' U' F2 D").unwrap());
+        let state = CubeState::default().apply_move_instances(&scramble);
+        let generated = scramble_for_state(state.clone());
+        assert_eq!(CubeState::default().apply_move_instances(&generated), state);
+
+        let state = CubeState::random_with_seed(7);
+        let generated = random_state_scramble_with_seed(7);
+        assert_eq!(CubeState::default().apply_move_instances(&generated), state);
+    }
+
+    // SERIALIZATION TESTS
+    #[cfg(feature = "serde")]
+    #[test]
+    fn moves_serialize_as_notation() {
+        let seq = MoveSequence(parse_scramble("R U2 F'").unwrap());
+        let json = serde_json::to_string(&seq).unwrap();
+        assert_eq!(json, "\"R U2 F'\"");
+        assert_eq!(serde_json::from_str::<MoveSequence>(&json).unwrap(), seq);
+
+        let m: MoveInstance = serde_json::from_str("\"D'\"").unwrap();
+        assert_eq!(m, MoveInstance::new(BaseMoveToken::D, Direction::Prime));
+        assert!(serde_json::from_str::<MoveInstance>("\"R U\"").is_err());
+        assert!(serde_json::from_str::<MoveInstance>("\"R'2\"").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn state_round_trips() {
+        let state = state_of("R U R' F2 D B'");
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<CubeState>(&json).unwrap(), state);
+        let bytes = bincode::serialize(&state).unwrap();
+        assert_eq!(bincode::deserialize::<CubeState>(&bytes).unwrap(), state);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn state_deserializes_from_either_form() {
+        let state = state_of("R U");
+        let facelets = state.to_facelet_string(&ColorScheme::default());
+        let json = format!("{{\"facelets\": \"{}\"}}", facelets);
+        assert_eq!(serde_json::from_str::<CubeState>(&json).unwrap(), state);
+
+        let json = format!(
+            "{{\"cp\": {:?}, \"co\": {:?}, \"ep\": {:?}, \"eo\": {:?}}}",
+            state.cp, state.co, state.ep, state.eo
+        );
+        assert_eq!(serde_json::from_str::<CubeState>(&json).unwrap(), state);
+
+        let solved = CubeState::default().to_facelet_string(&ColorScheme::default());
+        let json = format!(
+            "{{\"cp\": {:?}, \"co\": {:?}, \"ep\": {:?}, \"eo\": {:?}, \"facelets\": \"{}\"}}",
+            state.cp, state.co, state.ep, state.eo, solved
+        );
+        assert!(serde_json::from_str::<CubeState>(&json).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn invalid_pieces_fail_to_deserialize() {
+        let state = CubeState::default();
+        let json = format!(
+            "{{\"cp\": [0, 1, 2, 3, 4, 5, 6, 9], \"co\": {:?}, \"ep\": {:?}, \"eo\": {:?}}}",
+            state.co, state.ep, state.eo
+        );
+        let err = serde_json::from_str::<CubeState>(&json).unwrap_err();
+        assert!(err.to_string().contains("corner"));
+
+        let json = format!(
+            "{{\"cp\": {:?}, \"co\": [1, 0, 0, 0, 0, 0, 0, 0], \"ep\": {:?}, \"eo\": {:?}}}",
+            state.cp, state.ep, state.eo
+        );
+        assert!(serde_json::from_str::<CubeState>(&json).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn puzzle_types_round_trip() {
+        use rusty_rubik::puzzle::*;
+        let cube = Puzzle::make_move(
+            &Cube3::default(),
+            CubeMove {
+                axis: 3,
+                depth: 1,
+                rotation: 1,
+            },
+        )
+        .unwrap();
+        let json = serde_json::to_string(&cube).unwrap();
+        let back: Cube3 = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            (back.cp, back.co, back.ep, back.eo),
+            (cube.cp, cube.co, cube.ep, cube.eo)
+        );
+
+        for (m, notation) in [((3, 1, 1), "R"), ((4, 2, 2), "Fw2"), ((0, 3, 3), "3Uw'")] {
+            let m = CubeMove {
+                axis: m.0,
+                depth: m.1,
+                rotation: m.2,
+            };
+            let json = serde_json::to_string(&m).unwrap();
+            assert_eq!(json, format!("\"{}\"", notation));
+            assert_eq!(serde_json::from_str::<CubeMove>(&json).unwrap(), m);
+        }
+        for notation in ["0Rw", "00Rw2", "03Rw", "1Rw", "2Rw'", "3R", "Rw2'", "256Rw"] {
+            let json = format!("\"{}\"", notation);
+            assert!(
+                serde_json::from_str::<CubeMove>(&json).is_err(),
+                "{}",
+                notation
+            );
+        }
+    }
+
+    // LAST LAYER TESTS
+    #[test]
+    fn last_layer_case_counts() {
+        assert_eq!(get_case_names(CaseSet::OLL).len(), 58);
+        assert_eq!(get_case_names(CaseSet::PLL).len(), 22);
+        assert_eq!(get_case_names(CaseSet::COLL).len(), 43);
+        assert_eq!(get_case_names(CaseSet::ZBLL).len(), 494);
+        let t_cases = get_case_names(CaseSet::ZBLL)
+            .iter()
+            .filter(|name| name.starts_with('T') && name.len() > 1)
+            .count();
+        assert_eq!(t_cases, 72);
+
+        // the index within each corner shape is opaque, so it is set off by `#`
+        let coll = get_case_names(CaseSet::COLL);
+        let count = |shape: &str| {
+            coll.iter()
+                .filter(|name| name.split('#').next() == Some(shape))
+                .count()
+        };
+        assert!(coll[1..].iter().all(|name| name.contains('#')));
+        assert_eq!(
+            ["H", "Pi", "U", "T", "L", "S", "AS", "O"].map(count),
+            [4, 6, 6, 6, 6, 6, 6, 2]
+        );
+    }
+
+    #[test]
+    fn recognizes_oll_under_auf_and_rotation() {
+        // the inverse of a Sune, seen from another side
+        let state = state_of("y U2 R U2 R' U' R U' R' U");
+        let oll = recognize(&state, CaseSet::OLL).unwrap();
+        assert_eq!(oll.name, "27");
+
+        let mut solution = vec![];
+        solution.extend(
+            oll.pre_auf
+                .map(|dir| MoveInstance::new(BaseMoveToken::U, dir)),
+        );
+        solution.extend(oll.algorithm.unwrap().get_moves());
+        let oriented = state.apply_move_instances(&MoveSequence(solution));
+        assert!(is_f2l_solved(&oriented));
+        assert_eq!(recognize(&oriented, CaseSet::OLL).unwrap().name, "skip");
+    }
+
+    #[test]
+    fn recognized_pll_is_solved_by_its_aufs() {
+        let state = state_of("U' R2 U R U R' U' R' U' R' U R' U2");
+        let pll = recognize(&state, CaseSet::PLL).unwrap();
+        assert_eq!(pll.name, "Ua");
+
+        let u_turn =
+            |dir: Option<Direction>| dir.map(|dir| MoveInstance::new(BaseMoveToken::U, dir));
+        let mut solution = vec![];
+        solution.extend(u_turn(pll.pre_auf));
+        solution.extend(pll.algorithm.unwrap().get_moves());
+        solution.extend(u_turn(pll.post_auf));
+        assert_eq!(
+            state.apply_move_instances(&MoveSequence(solution)),
+            CubeState::default()
+        );
+    }
+
+    #[test]
+    fn recognition_requires_its_preconditions() {
+        assert_eq!(recognize(&state_of("R U R'"), CaseSet::OLL), None);
+        assert!(!is_f2l_solved(&state_of("R U R'")));
+        // an OLL case is not a PLL, COLL or ZBLL case
+        let state = state_of("F U R U' R' F'");
+        assert_eq!(recognize(&state, CaseSet::OLL).unwrap().name, "45");
+        assert_eq!(recognize(&state, CaseSet::PLL), None);
+        assert_eq!(recognize(&state, CaseSet::ZBLL), None);
+    }
+
+    #[test]
+    fn zbll_cases_are_recognized_after_aufs() {
+        let case = get_case_state(CaseSet::ZBLL, "U#5").unwrap();
+        let state = state_of("U").compose(&case).compose(&state_of("U2"));
+        let zbll = recognize(&state, CaseSet::ZBLL).unwrap();
+        assert_eq!(zbll.name, "U#5");
+        assert_eq!(zbll.pre_auf, Some(Direction::Double));
+        assert_eq!(zbll.post_auf, Some(Direction::Prime));
+        assert_eq!(
+            recognize(&state, CaseSet::COLL)
+                .unwrap()
+                .name
+                .chars()
+                .next(),
+            Some('U')
+        );
+    }
+
+    #[test]
+    fn last_layer_case_states_are_valid() {
+        for &set in [CaseSet::OLL, CaseSet::PLL, CaseSet::COLL, CaseSet::ZBLL].iter() {
+            for name in get_case_names(set) {
+                let state = get_case_state(set, &name).unwrap();
+                assert_eq!(state.validate(), Ok(()), "{:?} {}", set, name);
+                assert_eq!(recognize(&state, set).unwrap().name, name);
+            }
+        }
+    }
+
+    // ALGORITHM DATABASE TESTS
+    #[test]
+    fn builtin_database_case_counts() {
+        let db = AlgDatabase::builtin();
+        let count = |set| db.get_cases().iter().filter(|c| c.set == set).count();
+        assert_eq!(count(AlgSet::F2L), 41);
+        assert_eq!(count(AlgSet::OLL), 57);
+        assert_eq!(count(AlgSet::PLL), 21);
+        assert_eq!(count(AlgSet::COLL), 42);
+        assert_eq!(count(AlgSet::EOLL) + count(AlgSet::OCLL), 10);
+        assert_eq!(count(AlgSet::CPLL) + count(AlgSet::EPLL), 6);
+        assert_eq!(db.get_cases_with_tag("2look").len(), 16);
+    }
+
+    #[test]
+    fn looked_up_algorithms_solve_their_case() {
+        let db = AlgDatabase::builtin();
+        let state = state_of("U R U R' U' R' F R2 U' R' U' R U R' F' U2");
+        let found = db.lookup(&state, AlgSet::PLL).unwrap();
+        assert_eq!(found.case.name, "T");
+        for alg in found.algorithms.iter() {
+            assert_eq!(state.apply_move_instances(alg), CubeState::default());
+        }
+
+        // after a y rotation, the algorithms are given from the new side
+        let rotated = state_of("y").compose(&state);
+        for alg in db.lookup(&rotated, AlgSet::PLL).unwrap().algorithms.iter() {
+            let pll = recognize(&rotated.apply_move_instances(alg), CaseSet::PLL).unwrap();
+            assert_eq!(
+                (pll.name.as_str(), pll.pre_auf, pll.post_auf),
+                ("skip", None, None)
+            );
+        }
+        let sets: Vec<AlgSet> = db.lookup_all(&state).iter().map(|m| m.case.set).collect();
+        assert_eq!(sets, vec![AlgSet::PLL, AlgSet::CPLL, AlgSet::COLL]);
+    }
+
+    #[test]
+    fn coll_cases_match_last_layer_recognition() {
+        let db = AlgDatabase::builtin();
+        for case in db.get_cases().iter().filter(|c| c.set == AlgSet::COLL) {
+            let recognized = recognize(case.get_state(), CaseSet::COLL).unwrap();
+            assert_eq!(recognized.name, case.name);
+        }
+    }
+
+    #[test]
+    fn f2l_cases_use_the_standard_numbers() {
+        let db = AlgDatabase::builtin();
+        let name = |moves| {
+            db.lookup(&state_of(moves), AlgSet::F2L)
+                .unwrap()
+                .case
+                .name
+                .clone()
+        };
+        assert_eq!(name("R U R' U'"), "1");
+        assert_eq!(name("F' U F"), "3");
+        assert_eq!(name("R U' R'"), "4");
+        assert_eq!(name("R' U' R2 U' R2 U2 R"), "12");
+        assert_eq!(name("R U' R U2 F R2 F' U2 R2"), "37");
+    }
+
+    #[test]
+    fn f2l_cases_start_with_short_algorithms() {
+        let db = AlgDatabase::builtin();
+        for (name, insert) in [("3", "F' U F"), ("4", "R U' R'")].iter() {
+            for auf in ["", "U", "U2", "U'"].iter() {
+                let state = state_of(&format!("{} {}", insert, auf));
+                let found = db.lookup(&state, AlgSet::F2L).unwrap();
+                assert_eq!(found.case.name, *name);
+                let moves = found.algorithms[0].get_moves();
+                let without_auf = match moves[0].basemove {
+                    BaseMoveToken::U => &moves[1..],
+                    _ => &moves[..],
+                };
+                assert_eq!(without_auf.len(), 3, "{}", found.algorithms[0]);
+                assert!(is_f2l_solved(
+                    &state.apply_move_instances(&found.algorithms[0])
+                ));
+            }
+        }
+        for case in db.get_cases().iter().filter(|c| c.set == AlgSet::F2L) {
+            assert!(case.algorithms[0].get_moves().len() <= 12, "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn f2l_pairs_are_looked_up_in_every_slot() {
+        let db = AlgDatabase::builtin();
+        for moves in ["R U R'", "L' U' L", "B U2 B'", "L U L' U", "B' U' B"].iter() {
+            let state = state_of(moves);
+            let found = db.lookup(&state, AlgSet::F2L).unwrap();
+            assert_eq!(found.rotation.is_some(), !moves.starts_with('R'));
+            let mut solution: Vec<MoveInstance> = found
+                .rotation
+                .map(|dir| MoveInstance::new(BaseMoveToken::Y, dir))
+                .into_iter()
+                .collect();
+            solution.extend(found.algorithms[0].get_moves());
+            let solved = state.apply_move_instances(&MoveSequence(solution));
+            assert!(is_f2l_solved(&solved), "{}", moves);
+        }
+    }
+
+    #[test]
+    fn invalid_states_are_not_looked_up() {
+        let db = AlgDatabase::builtin();
+        let mut state = CubeState::default();
+        state.cp[0] = 1;
+        assert_eq!(db.lookup(&state, AlgSet::F2L), None);
+        assert!(db.lookup_all(&state).is_empty());
+    }
+
+    #[test]
+    fn csv_errors_report_their_line() {
+        let text = "# OLL\nOLL,Sune,R U R' U R U2 R'\nOLL,Sune,R U2 R' U' R U' R'";
+        assert_eq!(
+            AlgDatabase::from_csv(text).unwrap_err(),
+            AlgDatabaseError::AtLine(
+                3,
+                Box::new(AlgDatabaseError::CaseMismatch(
+                    "Sune".to_string(),
+                    "R U2 R' U' R U' R'".to_string()
+                ))
+            )
+        );
+        assert_eq!(
+            AlgDatabase::from_csv("PLL,T,R U R'").unwrap_err(),
+            AlgDatabaseError::AtLine(
+                1,
+                Box::new(AlgDatabaseError::NotInSet("R U R'".to_string()))
+            )
+        );
+        assert_eq!(
+            AlgDatabase::from_csv("ELL,x,R").unwrap_err(),
+            AlgDatabaseError::AtLine(1, Box::new(AlgDatabaseError::UnknownSet("ELL".to_string())))
+        );
+        let mut db = AlgDatabase::from_csv("OLL,Sune,R U R' U R U2 R'").unwrap();
+        assert_eq!(
+            db.extend_from_csv("OLL,27,U R U R' U R U2 R' U'"),
+            Err(AlgDatabaseError::AtLine(
+                1,
+                Box::new(AlgDatabaseError::DuplicateCase(
+                    "Sune".to_string(),
+                    "U R U R' U R U2 R' U'".to_string()
+                ))
+            ))
+        );
+    }
+
+    // STEP TESTS
+    #[test]
+    fn cfop_steps_are_found_on_any_side() {
+        let state = state_of("R U R'");
+        assert!(state.is_cross_solved());
+        assert!(state.is_f2l_pair_solved(0));
+        assert!(!state.is_f2l_pair_solved(1));
+        assert_eq!(state.count_solved_f2l_pairs(), 3);
+        assert!(!state.is_f2l_solved());
+
+        // turning F leaves the F2L and OLL of the B face solved,
+        // but not the D cross or its pairs
+        let state = state_of("F");
+        assert!(state.is_cross_solved());
+        assert!(state.is_oll_solved());
+        assert!(!state.is_f2l_pair_solved(0));
+        assert_eq!(state.count_solved_f2l_pairs(), 4);
+
+        let state = state_of("x F R U R' U' F'");
+        assert!(state.is_f2l_solved());
+        assert!(!state.is_oll_solved());
+        assert!(!state_of("R U").is_f2l_solved());
+    }
+
+    #[test]
+    fn eo_follows_the_centers() {
+        let state = state_of("F");
+        assert!(state.is_eo_solved(Axis::UD));
+        assert!(state.is_eo_solved(Axis::LR));
+        assert!(!state.is_eo_solved(Axis::FB));
+        // after y, F turns the face that was on R
+        let state = state_of("y F");
+        assert!(state.is_eo_solved(Axis::FB));
+        assert!(!state.is_eo_solved(Axis::LR));
+    }
+
+    #[test]
+    fn roux_steps_ignore_the_m_slice() {
+        let state = state_of("M' U R U' M2");
+        assert!(state.is_first_block_solved());
+        assert!(!state.is_second_block_solved());
+        assert!(!state.is_cross_solved());
+
+        let state = state_of("M U2");
+        assert!(state.is_second_block_solved());
+        assert!(state.is_cmll_solved());
+        assert!(!state_of("R U R' U R U2 R'").is_cmll_solved());
+    }
+
+    #[test]
+    fn domino_reduction_on_any_axis() {
+        assert!(state_of("R2 U F2 D' L2").is_domino_reduced());
+        assert!(!state_of("R2 U F2 D' L2 R").is_domino_reduced());
+        assert!(state_of("R U2 F2 L' B2").is_domino_reduced());
+        assert!(state_of("x R2 U").is_domino_reduced());
+
+        // a custom mask for the DF and DB edges, with the M slice free
+        let mask = StepMask {
+            edges: 1 << 8 | 1 << 10,
+            centers: 0b1100,
+            ..StepMask::default()
+        };
+        assert!(mask.is_solved(&state_of("M R2 L2 M")));
+        assert!(!mask.is_solved(&state_of("M R2 L2 M U")));
+    }
+
+    // RECONSTRUCTION TESTS
+    #[test]
+    fn reconstruction_checks_and_counts_steps() {
+        let solution = "
+            F U R U' R' F' // Cross @ 1.5
+            R U' // PLL @ 2.5
+            R' // insert
+        ";
+        let report = Reconstruction::parse("R U R' F R U R' U' F'", solution)
+            .unwrap()
+            .analyze();
+        assert_eq!(report.steps[0].goal, Some(StepGoal::Cross));
+        assert_eq!(report.steps[0].reached, Some(true));
+        assert_eq!(report.steps[1].goal, Some(StepGoal::Solved));
+        assert_eq!(report.steps[1].reached, Some(false));
+        assert_eq!(report.steps[1].duration, Some(1.0));
+        assert_eq!(report.steps[1].tps, Some(2.0));
+        assert_eq!(report.steps[2].reached, None);
+        assert_eq!(report.steps[2].duration, None);
+        assert!(report.solved);
+        assert!(!report.is_valid());
+        assert_eq!(report.counts.htm, 9);
+        assert_eq!(report.time, None);
+
+        let text = report.to_string();
+        assert!(
+            text.contains("PLL: R U' (2 HTM, 2 QTM, 2 STM, 2 ETM, 1.00s, 2.00 TPS) [not reached]")
+        );
+        assert!(text.ends_with("total: 9 HTM, 9 QTM, 9 STM, 9 ETM, solved"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn reconstruction_report_as_json() {
+        let solution = "U M2 x // LSE @ 0.5";
+        let report = Reconstruction::parse("M2 U'", solution).unwrap().analyze();
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["scramble"], "M2 U'");
+        assert_eq!(json["steps"][0]["label"], "LSE");
+        assert_eq!(json["steps"][0]["reached"], true);
+        assert_eq!(json["steps"][0]["counts"]["htm"], 3);
+        assert_eq!(json["steps"][0]["counts"]["stm"], 2);
+        assert_eq!(json["steps"][0]["counts"]["etm"], 3);
+        assert_eq!(json["steps"][0]["goal"], "Solved");
+        assert_eq!(json["steps"][0]["duration"], 0.5);
+        assert_eq!(json["time"], 0.5);
+        assert_eq!(json["tps"], 4.0);
+        assert_eq!(json["solved"], true);
+    }
+
+    #[test]
+    fn reconstruction_parse_errors() {
+        assert_eq!(
+            Reconstruction::parse("R Q", ""),
+            Err(ReconstructionError::InvalidScramble("R Q".to_string()))
+        );
+        assert_eq!(
+            Reconstruction::parse("R", "R' // cross\nR'"),
+            Err(ReconstructionError::MissingLabel(2))
+        );
+        assert_eq!(
+            Reconstruction::parse("R", "R' //  @ 1"),
+            Err(ReconstructionError::MissingLabel(1))
+        );
+        assert_eq!(
+            Reconstruction::parse("R", "R' // cross @ soon"),
+            Err(ReconstructionError::InvalidTime(1, "soon".to_string()))
+        );
+        assert_eq!(
+            Reconstruction::parse("R", "R3 // cross"),
+            Err(ReconstructionError::InvalidMoves(1, "R3".to_string()))
+        );
+        assert_eq!(
+            Reconstruction::parse("R", "R' // cross @ 2\nU // f2l\nU' // oll @ 1.5"),
+            Err(ReconstructionError::DecreasingTime(3, "1.5".to_string()))
+        );
+        assert!(Reconstruction::parse("R", "R' // cross @ 2\nU // f2l @ 2").is_ok());
+        assert_eq!("F2L 3".parse(), Ok(StepGoal::Pairs(3)));
+        assert!("f2l5".parse::<StepGoal>().is_err());
+    }
+
+    // MASKED STATE TESTS
+    #[test]
+    fn masking_commutes_with_moves() {
+        let seq = MoveSequence(parse_scramble("R U2 F' M D2 x L' E").unwrap());
+        for mask in [
+            PieceMask::all(),
+            PieceMask::oll(),
+            PieceMask::domino(),
+            PieceMask::eo(),
+        ]
+        .iter()
+        {
+            for seed in 0..10 {
+                let state = CubeState::random_with_seed(seed);
+                assert_eq!(
+                    MaskedState::new(&state, mask).apply_move_instances(&seq),
+                    MaskedState::new(&state.apply_move_instances(&seq), mask)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn masked_states_reach_partial_goals() {
+        let t_perm = state_of("R U R' U' R' F R2 U' R' U' R U R' F'");
+        assert!(MaskedState::new(&t_perm, &PieceMask::oll()).is_solved());
+        assert!(!MaskedState::new(&t_perm, &PieceMask::all()).is_solved());
+        assert!(MaskedState::new(&state_of("R2 U F2 D'"), &PieceMask::domino()).is_solved());
+        assert!(!MaskedState::new(&state_of("R2 U F"), &PieceMask::domino()).is_solved());
+        assert!(MaskedState::new(&state_of("R U L' D"), &PieceMask::eo()).is_solved());
+
+        let goal = MaskedState::solved(&PieceMask::cross());
+        assert!(goal.matches(&state_of("R U R'")));
+        assert!(!goal.matches(&state_of("R")));
+    }
+
+    #[test]
+    fn masked_states_hash_by_what_is_seen() {
+        let count_distinct = |mask: &PieceMask| {
+            ["", "U", "U2", "U'"]
+                .iter()
+                .map(|moves| MaskedState::new(&state_of(moves), mask))
+                .collect::<std::collections::HashSet<_>>()
+                .len()
+        };
+        assert_eq!(count_distinct(&PieceMask::all()), 4);
+        assert_eq!(count_distinct(&PieceMask::oll()), 1);
+
+        // a breadth-first search for the cross, over masked states
+        let mut frontier = vec![MaskedState::new(&state_of("F2 R"), &PieceMask::cross())];
+        let mut seen: std::collections::HashSet<_> = frontier.iter().cloned().collect();
+        let mut depth = 0;
+        while !frontier.iter().any(|state| state.is_solved()) {
+            frontier = frontier
+                .iter()
+                .flat_map(|state| ALL_MOVES.iter().map(move |m| state.apply_move_instance(m)))
+                .filter(|state| seen.insert(state.clone()))
+                .collect();
+            depth += 1;
+        }
+        assert_eq!(depth, 2);
+    }
+
+    // PARTIAL GOAL TESTS
+    #[test]
+    fn ida_solves_optimal_cross() {
+        let goal = MaskedGoal::new(&MaskedState::solved(&PieceMask::cross()));
+        let state = CubeState::random_with_seed(1);
+        let solution = GoalSolver::new(state.clone(), &goal).solve().unwrap();
+        assert!(goal.is_reached(&state.apply_move_instances(&solution)));
+        assert!(state.apply_move_instances(&solution).is_cross_solved());
+
+        // no shorter solution exists
+        let mut frontier = std::collections::HashSet::new();
+        frontier.insert(MaskedState::new(&state, &PieceMask::cross()));
+        for _ in 1..solution.get_moves().len() {
+            frontier = frontier
+                .iter()
+                .flat_map(|state| ALL_MOVES.iter().map(move |m| state.apply_move_instance(m)))
+                .collect();
+            assert!(!frontier.iter().any(|state| state.is_solved()));
+        }
+    }
+
+    #[test]
+    fn ida_solves_first_block() {
+        let goal = MaskedGoal::new(&MaskedState::solved(&PieceMask::first_block()));
+        let state = state_of("R U2 F' L D' B2 R'");
+        let solution = GoalSolver::new(state.clone(), &goal).solve().unwrap();
+        assert!(solution.get_moves().len() <= 7);
+        assert!(state
+            .apply_move_instances(&solution)
+            .is_first_block_solved());
+        assert_eq!(
+            GoalSolver::new(CubeState::default(), &goal).solve(),
+            Some(MoveSequence(vec![]))
+        );
+    }
+
+    #[test]
+    fn ida_solves_to_a_predicate() {
+        let cross = MaskedGoal::new(&MaskedState::solved(&PieceMask::cross()));
+        // every state with the DFR pair solved has the cross solved too
+        let goal = PredicateGoal::new(|state: &CubeState| state.is_f2l_pair_solved(1), &cross);
+        let state = state_of("R U R' F");
+        let solution = GoalSolver::new(state.clone(), &goal).solve().unwrap();
+        assert_eq!(solution.get_moves().len(), 4);
+        assert!(state.apply_move_instances(&solution).is_f2l_pair_solved(1));
+    }
+
+    #[test]
+    fn goal_solves_stop_at_their_max_depth() {
+        let cross = MaskedGoal::new(&MaskedState::solved(&PieceMask::cross()));
+        let solver = GoalSolver::new(state_of("R"), &cross);
+        assert_eq!(solver.solve_within(0), None);
+        assert_eq!(
+            solver.solve_within(1),
+            Some(MoveSequence(parse_scramble("R'").unwrap()))
+        );
+
+        let never = PredicateGoal::new(|_: &CubeState| false, &cross);
+        assert_eq!(
+            GoalSolver::new(CubeState::default(), &never).solve_within(4),
+            None
+        );
+    }
+
+    #[test]
+    fn unreachable_masked_goals_are_not_solved() {
+        // a single flipped edge cannot be reached by any moves
+        let mut flipped = CubeState::default();
+        flipped.eo[0] = 1;
+        let goal = MaskedGoal::new(&MaskedState::new(&flipped, &PieceMask::eo()));
+        assert_eq!(GoalSolver::new(state_of("R U F"), &goal).solve(), None);
+        assert!(GoalSolver::new(state_of("F"), &goal).solve().is_none());
+    }
+
+    #[test]
+    fn goal_solves_handle_moved_centers() {
+        let cross = MaskedGoal::new(&MaskedState::solved(&PieceMask::cross()));
+        for moves in ["M", "x", "E y'"].iter() {
+            let state = state_of(moves);
+            let solution = GoalSolver::new(state.clone(), &cross).solve().unwrap();
+            assert!(cross.is_reached(&state.apply_move_instances(&solution)));
+        }
+    }
+
     // PRUNING TABLE TESTS
+    #[test]
+    fn generate_eo_pruning_table() {
+        let path = std::env::temp_dir().join("rusty_rubik_test_edges_o.pt");
+        let filename = path.to_str().unwrap().to_string();
+        assert!(generate_pruning_table_eo(filename));
+        let table = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(table.len(), 2048);
+        assert_eq!(table[0], 0);
+        // every edge orientation is reachable within 7 moves
+        assert!(table[1..].iter().all(|d| (1..=7).contains(d)));
+        let f = CubeState::default().apply_move_instance(&cube_move!(F, Normal));
+        let (_, eo, _) = get_index_of_state(&f);
+        assert_eq!(table[eo as usize], 1);
+    }
+
     #[test]
     #[ignore]
     fn pruning_table_of_solved_is_zero() {
@@ -110,4 +1866,15 @@ mod tests {
         let solution = solver.solve();
         assert_eq!(solution.get_moves().len(), 9);
     }
+
+    #[test]
+    #[ignore]
+    fn ida_solves_moved_centers() {
+        let tables = PruningTables::default_tables();
+        for moves in ["M", "x"].iter() {
+            let state = state_of(moves);
+            let solution = IDASolver::new(state.clone(), &tables).solve();
+            assert_eq!(state.apply_move_instances(&solution), CubeState::default());
+        }
+    }
 }